@@ -0,0 +1,209 @@
+//! Throughput of turning Postgres rows into JSON: the decoders resolved once
+//! per result set against the `try_get` cascade `Db::row_to_json` used to
+//! run for every cell.
+//!
+//! The rows come from a real server:
+//!
+//! ```bash
+//! MULTI_QUERY_BENCH_URL=postgresql://postgres@localhost/postgres \
+//!     cargo bench --bench row_decoding
+//! ```
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde_json::{Map, Value, json};
+use sqlx::postgres::{PgPool, PgRow};
+use sqlx::types::{BigDecimal, JsonValue};
+use sqlx::{Column, Postgres, Row, ValueRef};
+
+#[path = "../src/app/decoders.rs"]
+#[allow(dead_code)]
+mod decoders;
+#[path = "../src/app/types.rs"]
+#[allow(dead_code)]
+mod types;
+
+use decoders::{Formats, RowDecoder};
+use types::{ByteaEncoding, ConnectionString, InetOutput, NumericOutput};
+
+const ROWS: usize = 20_000;
+const ROUNDS: u32 = 5;
+
+/// A bit of everything `row_to_json` handles, with some nulls in between.
+const QUERY: &str = "
+    SELECT
+        g AS id,
+        g::SMALLINT % 100 AS small,
+        g::BIGINT * 1000 AS big,
+        g::REAL / 3 AS ratio,
+        g::DOUBLE PRECISION / 7 AS score,
+        (g / 4.0)::NUMERIC(12, 2) AS amount,
+        g % 2 = 0 AS even,
+        'customer-' || g AS name,
+        CASE WHEN g % 3 = 0 THEN NULL ELSE 'note' END AS note,
+        TIMESTAMP '2024-01-15 10:30:00' + g * INTERVAL '1 minute' AS created,
+        TIMESTAMPTZ '2024-01-15 10:30:00+00' AS updated,
+        DATE '2024-01-15' AS day,
+        jsonb_build_object('id', g) AS doc,
+        ARRAY[g, g + 1, g + 2] AS ids,
+        ARRAY['a', 'b'] AS tags
+    FROM generate_series(1, $1) AS g
+";
+
+#[tokio::main]
+async fn main() {
+    let Ok(url) = std::env::var("MULTI_QUERY_BENCH_URL") else {
+        eprintln!("Set MULTI_QUERY_BENCH_URL to a Postgres to benchmark");
+        return;
+    };
+
+    let pool = PgPool::connect(&url).await.expect("Failed to connect");
+    let rows: Vec<PgRow> = sqlx::query(QUERY)
+        .bind(ROWS as i32)
+        .fetch_all(&pool)
+        .await
+        .expect("Failed to fetch rows");
+
+    let cascade = measure(|| {
+        for row in &rows {
+            black_box(try_get_cascade(row));
+        }
+    });
+    let registry = measure(|| {
+        let mut decoder = RowDecoder::new(&rows[0], Formats::default());
+        for row in &rows {
+            black_box(decode_row(row, &mut decoder));
+        }
+    });
+
+    report("try_get cascade", cascade);
+    report("decoder registry", registry);
+    println!(
+        "{:>18}  {:.1}x",
+        "speedup",
+        cascade.as_secs_f64() / registry.as_secs_f64()
+    );
+}
+
+/// Best of `ROUNDS`, after one round to warm up.
+fn measure(mut run: impl FnMut()) -> Duration {
+    run();
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, elapsed: Duration) {
+    println!(
+        "{:>18}  {:>8.2} ms  {:>10.0} rows/s",
+        name,
+        elapsed.as_secs_f64() * 1000.0,
+        ROWS as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn decode_row(row: &PgRow, decoder: &mut RowDecoder) -> Map<String, Value> {
+    row.columns()
+        .iter()
+        .map(|column| {
+            (column.name().to_string(), decoder.decode(row, column.ordinal()))
+        })
+        .collect()
+}
+
+/// What `Db::row_to_json` did before the decoders.
+fn try_get_cascade(row: &PgRow) -> Map<String, Value> {
+    let mut json_obj = Map::new();
+
+    for col in row.columns() {
+        let name = col.name();
+
+        let json_value: Value = if let Ok(Some(v)) =
+            row.try_get::<Option<i16>, _>(name)
+        {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<i32>, _>(name) {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<i64>, _>(name) {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<f32>, _>(name) {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<f64>, _>(name) {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<BigDecimal>, _>(name)
+        {
+            match v.to_string().parse::<f64>() {
+                Ok(num) => json!(num),
+                Err(_) => Value::String(v.to_string()),
+            }
+        } else if let Ok(Some(v)) = row.try_get::<Option<bool>, _>(name) {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<String>, _>(name) {
+            Value::String(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<&str>, _>(name) {
+            Value::String(v.to_string())
+        } else if let Ok(Some(v)) =
+            row.try_get::<Option<NaiveDateTime>, _>(name)
+        {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<NaiveDate>, _>(name) {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<NaiveTime>, _>(name) {
+            json!(v)
+        } else if let Ok(Some(v)) =
+            row.try_get::<Option<DateTime<Utc>>, _>(name)
+        {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<JsonValue>, _>(name) {
+            v
+        } else if let Ok(Some(v)) = row.try_get::<Option<Vec<String>>, _>(name)
+        {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<Vec<i32>>, _>(name) {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<Vec<i64>>, _>(name) {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<Vec<f64>>, _>(name) {
+            json!(v)
+        } else if let Ok(Some(v)) = row.try_get::<Option<Vec<bool>>, _>(name) {
+            json!(v)
+        } else if let Ok(None) = row.try_get::<Option<i16>, _>(name) {
+            Value::Null
+        } else if let Ok(None) = row.try_get::<Option<i32>, _>(name) {
+            Value::Null
+        } else if let Ok(None) = row.try_get::<Option<i64>, _>(name) {
+            Value::Null
+        } else if let Ok(None) = row.try_get::<Option<f32>, _>(name) {
+            Value::Null
+        } else if let Ok(None) = row.try_get::<Option<f64>, _>(name) {
+            Value::Null
+        } else if let Ok(None) = row.try_get::<Option<BigDecimal>, _>(name) {
+            Value::Null
+        } else if let Ok(None) = row.try_get::<Option<bool>, _>(name) {
+            Value::Null
+        } else if let Ok(None) = row.try_get::<Option<String>, _>(name) {
+            Value::Null
+        } else {
+            match row.try_get_raw(name) {
+                Ok(raw) if !raw.is_null() => {
+                    match <String as sqlx::Decode<Postgres>>::decode(raw) {
+                        Ok(s) => Value::String(s),
+                        Err(_) => Value::Null,
+                    }
+                }
+                _ => Value::Null,
+            }
+        };
+
+        json_obj.insert(name.to_string(), json_value);
+    }
+
+    json_obj
+}
@@ -0,0 +1,241 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use serde::de::{self, Deserializer, MapAccess};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, to_string};
+use tokio_stream::StreamExt;
+
+use crate::{
+    ClickHouseDb, ConnectionString, Db, DuckDb, FileDb, MySqlDb,
+    NumericOutput, ParquetDb, SqliteDb,
+    decoders::{self, NumberKind},
+};
+
+/// A single result row, already converted into the JSON object printed to
+/// stdout.
+pub type JsonRow = Map<String, Value>;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ColumnDescription {
+    pub name: String,
+    pub type_name: String,
+}
+
+/// Produces unique JSON keys for result columns: repeated column names get a
+/// `_1`, `_2`, ... suffix so no value is overwritten.
+#[derive(Default)]
+pub struct ColumnKeys {
+    key_count: HashMap<String, usize>,
+}
+
+impl ColumnKeys {
+    pub fn next(&mut self, name: &str) -> String {
+        let count = self
+            .key_count
+            .entry(name.to_string())
+            .and_modify(|c| *c += 1)
+            .or_insert(0);
+
+        if *count == 0 {
+            name.to_string()
+        } else {
+            format!("{}_{}", name, count)
+        }
+    }
+}
+
+/// The columns of a row that a backend hands over as a JSON object, in
+/// result order. Unlike `Value`, repeated column names are kept.
+pub struct JsonColumns(pub Vec<(String, Value)>);
+
+impl JsonColumns {
+    /// The row as printed, tagged with `db_name`.
+    pub fn into_row(self, db_name: &str) -> JsonRow {
+        let mut json_obj = Map::new();
+        let mut keys = ColumnKeys::default();
+
+        json_obj.insert("db_name".to_string(), Value::String(db_name.into()));
+
+        for (name, value) in self.0 {
+            json_obj.insert(keys.next(&name), value);
+        }
+
+        json_obj
+    }
+
+    /// Writes the numbers of each column as the kind at its position says.
+    pub fn with_numbers(
+        self,
+        kinds: &[NumberKind],
+        numeric: NumericOutput,
+    ) -> Self {
+        let columns =
+            self.0.into_iter().enumerate().map(|(i, (name, value))| {
+                let kind = kinds.get(i).copied().unwrap_or(NumberKind::Plain);
+                (name, decoders::number_to_json(value, kind, numeric))
+            });
+
+        JsonColumns(columns.collect())
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonColumns {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = JsonColumns;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<JsonColumns, A::Error> {
+                let mut columns = Vec::new();
+                while let Some((name, value)) = map.next_entry()? {
+                    columns.push((name, value));
+                }
+
+                Ok(JsonColumns(columns))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+/// A database engine a query can be fanned out to.
+#[async_trait]
+pub trait Backend: Send + Sync {
+    async fn connect(connection_string: ConnectionString) -> Result<Self>
+    where
+        Self: Sized;
+
+    fn name(&self) -> &str;
+
+    /// SQL dialects this backend understands, most specific first. They pick
+    /// the query file variant, e.g. `report.postgres.sql`.
+    fn dialects(&self) -> &'static [&'static str];
+
+    /// Streams the rows produced by `query`, each one tagged with `db_name`.
+    fn fetch<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<JsonRow>>;
+
+    async fn describe(&self, query: &str) -> Result<Vec<ColumnDescription>>;
+
+    async fn close(&self);
+
+    /// Problems with values of the rows written so far that didn't fail the
+    /// query, such as columns written as null, each reported once.
+    fn take_warnings(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Prints the rows of `query` as JSON lines, counting them in
+    /// `written`.
+    async fn query(&self, query: &str, written: &mut u64) -> Result<()> {
+        let mut rows = self.fetch(query);
+        while let Some(row) = rows.try_next().await? {
+            println!("{}", to_string(&row)?);
+            *written += 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    Postgres,
+    MySql,
+    Sqlite,
+    DuckDb,
+    ClickHouse,
+    File,
+    Parquet,
+}
+
+impl BackendKind {
+    pub fn from_uri(uri: &str) -> Result<Self> {
+        let Some((scheme, _)) = uri.split_once("://") else {
+            bail!("Connection URI '{}' has no scheme", uri);
+        };
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "postgres" | "postgresql" => Ok(Self::Postgres),
+            "mysql" | "mariadb" => Ok(Self::MySql),
+            "sqlite" => Ok(Self::Sqlite),
+            "duckdb" => Ok(Self::DuckDb),
+            "clickhouse" | "clickhouse+http" | "clickhouse+https" => {
+                Ok(Self::ClickHouse)
+            }
+            "file" => Ok(Self::File),
+            "parquet" => Ok(Self::Parquet),
+            other => bail!("Unsupported connection scheme '{}'", other),
+        }
+    }
+}
+
+/// Connects to the database behind `connection_string`. A Postgres entry
+/// with a `schemas` pattern yields one backend per tenant schema.
+pub async fn connect(
+    connection_string: ConnectionString,
+) -> Result<Vec<Arc<dyn Backend>>> {
+    let kind = BackendKind::from_uri(&connection_string.uri)?;
+    if connection_string.tls.is_some() && kind != BackendKind::Postgres {
+        bail!(
+            "TLS settings for '{}' are only supported for Postgres",
+            connection_string.name
+        );
+    }
+    if !connection_string.endpoints.is_empty() && kind != BackendKind::Postgres
+    {
+        bail!(
+            "Endpoint lists for '{}' are only supported for Postgres",
+            connection_string.name
+        );
+    }
+    if connection_string.ssh.is_some() && kind != BackendKind::Postgres {
+        bail!(
+            "SSH tunnels for '{}' are only supported for Postgres",
+            connection_string.name
+        );
+    }
+
+    let backend: Arc<dyn Backend> = match kind {
+        BackendKind::Postgres => {
+            return Ok(Db::connect_schemas(connection_string)
+                .await?
+                .into_iter()
+                .map(|db| Arc::new(db) as Arc<dyn Backend>)
+                .collect());
+        }
+        BackendKind::MySql => {
+            Arc::new(MySqlDb::connect(connection_string).await?)
+        }
+        BackendKind::Sqlite => {
+            Arc::new(SqliteDb::connect(connection_string).await?)
+        }
+        BackendKind::DuckDb => {
+            Arc::new(DuckDb::connect(connection_string).await?)
+        }
+        BackendKind::ClickHouse => {
+            Arc::new(ClickHouseDb::connect(connection_string).await?)
+        }
+        BackendKind::File => {
+            Arc::new(FileDb::connect(connection_string).await?)
+        }
+        BackendKind::Parquet => {
+            Arc::new(ParquetDb::connect(connection_string).await?)
+        }
+    };
+
+    Ok(vec![backend])
+}
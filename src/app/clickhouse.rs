@@ -0,0 +1,271 @@
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use futures::stream::{self, BoxStream};
+use percent_encoding::percent_decode_str;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio_stream::StreamExt;
+use url::Url;
+
+use crate::{
+    Backend, ColumnDescription, ConnectionString, JsonColumns, JsonRow,
+    NumericOutput, decoders::NumberKind,
+};
+
+/// ClickHouse backend over the HTTP interface, selected by `clickhouse://`,
+/// `clickhouse+http://` and `clickhouse+https://` URIs.
+///
+/// Results come back as JSONCompactEachRowWithNamesAndTypes: a line of
+/// column names, one of their types, then one array per row. The types pick
+/// the columns whose numbers are written as `--numeric` says.
+pub struct ClickHouseDb {
+    pub name: String,
+    client: Client,
+    endpoint: Url,
+    user: Option<String>,
+    password: Option<String>,
+    numeric: NumericOutput,
+}
+
+impl ClickHouseDb {
+    pub async fn new(
+        ConnectionString { name, uri, numeric, .. }: ConnectionString,
+    ) -> Result<Self> {
+        let uri = Url::parse(&uri).with_context(|| {
+            format!("Invalid ClickHouse URI for '{}'", name)
+        })?;
+
+        let (scheme, default_port) = match uri.scheme() {
+            "clickhouse+https" => ("https", 8443),
+            _ => ("http", 8123),
+        };
+        let host = uri.host_str().ok_or_else(|| {
+            anyhow!("ClickHouse URI for '{}' has no host", name)
+        })?;
+
+        let mut endpoint = Url::parse(&format!(
+            "{}://{}:{}/",
+            scheme,
+            host,
+            uri.port().unwrap_or(default_port)
+        ))?;
+
+        endpoint
+            .query_pairs_mut()
+            .extend_pairs(uri.query_pairs())
+            .append_pair(
+                "default_format",
+                "JSONCompactEachRowWithNamesAndTypes",
+            )
+            .append_pair("output_format_json_quote_64bit_integers", "0")
+            .append_pair("output_format_decimal_trailing_zeros", "1");
+
+        let database = uri.path().trim_start_matches('/');
+        if !database.is_empty() {
+            endpoint.query_pairs_mut().append_pair("database", database);
+        }
+
+        let user =
+            Some(uri.username()).filter(|user| !user.is_empty()).map(|user| {
+                percent_decode_str(user).decode_utf8_lossy().to_string()
+            });
+        let password = uri.password().map(|password| {
+            percent_decode_str(password).decode_utf8_lossy().to_string()
+        });
+
+        let db = Self {
+            name,
+            client: Client::new(),
+            endpoint,
+            user,
+            password,
+            numeric: numeric.unwrap_or_default(),
+        };
+        db.run::<Value>("SELECT 1").collect::<Result<Vec<_>>>().await?;
+
+        Ok(db)
+    }
+
+    /// Sends `query` and yields one parsed JSON value per line of the
+    /// response as it arrives.
+    fn run<'a, T: DeserializeOwned + Send + 'a>(
+        &'a self,
+        query: &'a str,
+    ) -> BoxStream<'a, Result<T>> {
+        let response = async move {
+            let mut request = self
+                .client
+                .post(self.endpoint.clone())
+                .body(query.to_string());
+
+            if let Some(user) = &self.user {
+                request = request.header("X-ClickHouse-User", user);
+            }
+            if let Some(password) = &self.password {
+                request = request.header("X-ClickHouse-Key", password);
+            }
+
+            let response = request.send().await?;
+            if !response.status().is_success() {
+                bail!("{}", response.text().await?.trim());
+            }
+
+            Ok(response.bytes_stream())
+        };
+
+        Box::pin(
+            stream::once(response)
+                .map_ok(|body| {
+                    split_lines(Box::pin(body.map(|chunk| Ok(chunk?))))
+                })
+                .try_flatten()
+                .map(|line| {
+                    let line = line?;
+                    serde_json::from_str(&line).with_context(|| {
+                        format!("Unexpected ClickHouse output '{}'", line)
+                    })
+                }),
+        )
+    }
+
+    /// Reads the names and types heading the response to `query`, and
+    /// streams the rows below them.
+    async fn rows<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> Result<BoxStream<'a, Result<JsonRow>>> {
+        let mut lines = self.run::<Vec<Value>>(query);
+        let names = header(lines.next().await.transpose()?);
+        let kinds: Vec<_> = header(lines.next().await.transpose()?)
+            .iter()
+            .map(|type_name| number_kind(type_name))
+            .collect();
+
+        Ok(Box::pin(lines.map_ok(move |values| {
+            JsonColumns(names.iter().cloned().zip(values).collect())
+                .with_numbers(&kinds, self.numeric)
+                .into_row(&self.name)
+        })))
+    }
+}
+
+/// How the numbers of a column of `type_name` are written, looking through
+/// `Nullable`, `LowCardinality` and `Array`.
+fn number_kind(type_name: &str) -> NumberKind {
+    let mut inner = type_name;
+    while let Some(wrapped) = ["Nullable(", "LowCardinality(", "Array("]
+        .iter()
+        .find_map(|wrapper| inner.strip_prefix(wrapper))
+        .and_then(|wrapped| wrapped.strip_suffix(')'))
+    {
+        inner = wrapped;
+    }
+
+    if matches!(inner, "Int128" | "Int256" | "UInt128" | "UInt256") {
+        return NumberKind::Wide;
+    }
+
+    // Decimal(P, S), Decimal(P) with no fractional digits, or Decimal32(S)
+    // and its wider siblings
+    let scale = inner
+        .strip_suffix(')')
+        .and_then(|inner| inner.split_once('('))
+        .and_then(|(family, args)| match (family, args.split_once(',')) {
+            ("Decimal", Some((_, scale))) => scale.trim().parse().ok(),
+            ("Decimal", None) => Some(0),
+            (family, None) if family.starts_with("Decimal") => {
+                args.trim().parse().ok()
+            }
+            _ => None,
+        });
+
+    scale.map_or(NumberKind::Plain, NumberKind::Decimal)
+}
+
+/// The strings of a line heading a response, the column names or their
+/// types.
+fn header(line: Option<Vec<Value>>) -> Vec<String> {
+    line.unwrap_or_default()
+        .into_iter()
+        .map(|value| value.as_str().unwrap_or("").to_string())
+        .collect()
+}
+
+/// Re-chunks a byte stream into non-empty lines.
+fn split_lines<'a, B: AsRef<[u8]> + Send + 'a>(
+    chunks: BoxStream<'a, Result<B>>,
+) -> BoxStream<'a, Result<String>> {
+    Box::pin(stream::try_unfold(
+        (chunks, Vec::new(), false),
+        |(mut chunks, mut buffer, mut done)| async move {
+            loop {
+                if let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=end).collect();
+                    let line = String::from_utf8(line)?.trim().to_string();
+                    if line.is_empty() {
+                        continue;
+                    }
+
+                    return Ok(Some((line, (chunks, buffer, done))));
+                }
+
+                if done {
+                    let line = String::from_utf8(std::mem::take(&mut buffer))?
+                        .trim()
+                        .to_string();
+                    if line.is_empty() {
+                        return Ok(None);
+                    }
+
+                    return Ok(Some((line, (chunks, buffer, done))));
+                }
+
+                match chunks.next().await {
+                    Some(chunk) => buffer.extend_from_slice(chunk?.as_ref()),
+                    None => done = true,
+                }
+            }
+        },
+    ))
+}
+
+#[async_trait]
+impl Backend for ClickHouseDb {
+    async fn connect(connection_string: ConnectionString) -> Result<Self> {
+        Self::new(connection_string).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dialects(&self) -> &'static [&'static str] {
+        &["clickhouse"]
+    }
+
+    fn fetch<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<JsonRow>> {
+        Box::pin(stream::once(self.rows(query)).try_flatten())
+    }
+
+    async fn describe(&self, query: &str) -> Result<Vec<ColumnDescription>> {
+        let query = query.trim().trim_end_matches(';');
+        let statement = format!("DESCRIBE TABLE ({})", query);
+
+        // Past the names and types of DESCRIBE's own columns, each line
+        // starts with the name and type of a column of the query
+        let columns: Vec<Vec<Value>> =
+            self.run(&statement).skip(2).collect::<Result<_>>().await?;
+
+        Ok(columns
+            .into_iter()
+            .map(|column| ColumnDescription {
+                name: column[0].as_str().unwrap_or("").to_string(),
+                type_name: column[1].as_str().unwrap_or("").to_string(),
+            })
+            .collect())
+    }
+
+    async fn close(&self) {}
+}
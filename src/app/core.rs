@@ -1,64 +1,408 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
 use futures::future::try_join_all;
-use tokio::{fs::File, io::AsyncReadExt, spawn};
+use tokio::{
+    fs::File,
+    io::AsyncReadExt,
+    spawn,
+    time::{Instant, sleep, timeout_at},
+};
+use tracing::warn;
 
-use crate::{ConnectionString, Db};
+use crate::{
+    Backend, ByteaSettings, ConnectionString, ErrorLog, ErrorRecord,
+    ErrorStage, InetOutput, NumericOutput, Summary, TimedOut, connect,
+    duration::seconds, retry, targets,
+};
 
 pub struct App {
-    pub databases: Vec<Arc<Db>>,
+    pub databases: Vec<Arc<dyn Backend>>,
+    /// Settings of each of `databases`, in the same order.
+    settings: Vec<QuerySettings>,
+    /// When the run has to be done, counted from before connecting.
+    deadline: Option<(Instant, TimedOut)>,
     pub path_to_query: PathBuf,
+    pub errors: ErrorLog,
+    pub on_error: OnError,
+    /// Entries left out because they failed to connect.
+    pub unreachable: Vec<String>,
+    /// Whether failures are tolerated, which makes a summary worth writing.
+    summarize: bool,
+}
+
+/// How a run deals with databases that fail.
+pub struct RunOptions {
+    /// Report entries that fail to connect and go on without them, instead
+    /// of aborting the run.
+    pub skip_unreachable: bool,
+    pub on_error: OnError,
+    pub errors: ErrorLog,
+    /// Seconds the query may run on each database, unless its entry says
+    /// otherwise.
+    pub timeout: Option<f64>,
+    /// Seconds the query may run on all databases together.
+    pub deadline: Option<f64>,
+    /// How NUMERIC columns are written, unless the entry says otherwise.
+    pub numeric: Option<NumericOutput>,
+    /// How bytea columns are written, unless the entry says otherwise.
+    pub bytea: ByteaSettings,
+    /// How inet and cidr columns are written, unless the entry says
+    /// otherwise.
+    pub inet: Option<InetOutput>,
+}
+
+/// How the query runs on one database, taken from its entry.
+#[derive(Debug, Clone, Copy, Default)]
+struct QuerySettings {
+    timeout: Option<Duration>,
+    retry: retry::Policy,
+}
+
+/// What happens when the query fails on a database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Fail the run with the first error.
+    #[default]
+    Abort,
+    /// Write an error record for the database and go on with the others.
+    Continue,
 }
 
 impl App {
     pub async fn new(
         connection_strings: Vec<ConnectionString>,
         path_to_query: PathBuf,
+        RunOptions {
+            skip_unreachable,
+            on_error,
+            errors,
+            timeout,
+            deadline,
+            numeric,
+            bytea,
+            inet,
+        }: RunOptions,
     ) -> Result<Self> {
+        let start = Instant::now();
+        let deadline = match deadline {
+            Some(deadline) => {
+                let deadline = seconds(deadline, "deadline")?;
+                Some((start + deadline, TimedOut::Deadline(deadline)))
+            }
+            None => None,
+        };
+
+        // Entries that run out of time while connecting are reported like
+        // databases that run out of time with the query
+        let tolerated = |err: &anyhow::Error| {
+            skip_unreachable
+                || (on_error == OnError::Continue && err.is::<TimedOut>())
+        };
+
+        // Listing the databases behind a server pattern connects already, an
+        // entry that fails there is as unreachable as one that fails later
+        let mut unreachable = Vec::new();
+        let mut expanded = Vec::with_capacity(connection_strings.len());
+        for connection_string in connection_strings {
+            let name = connection_string.name.clone();
+            match until(deadline, targets::expand(vec![connection_string]))
+                .await
+            {
+                Ok(targets) => expanded.extend(targets),
+                Err(err) if tolerated(&err) => {
+                    errors.write(&ErrorRecord::new(
+                        &name,
+                        ErrorStage::Connect,
+                        &err,
+                    ))?;
+                    unreachable.push(name);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        let mut connection_strings = expanded;
+        let mut entry_settings = Vec::with_capacity(connection_strings.len());
+        for connection_string in &mut connection_strings {
+            // Postgres entries pass it on as `statement_timeout`
+            connection_string.timeout = connection_string.timeout.or(timeout);
+            connection_string.numeric = connection_string.numeric.or(numeric);
+            connection_string.inet = connection_string.inet.or(inet);
+            let entry = connection_string.bytea.get_or_insert_default();
+            entry.encoding = entry.encoding.or(bytea.encoding);
+            entry.max_bytes = entry.max_bytes.or(bytea.max_bytes);
+            let timeout = match connection_string.timeout {
+                Some(timeout) => Some(seconds(
+                    timeout,
+                    &format!("timeout for '{}'", connection_string.name),
+                )?),
+                None => None,
+            };
+            let retry = retry::Policy::new(connection_string.retry.as_ref())?;
+            entry_settings.push(QuerySettings { timeout, retry });
+        }
+
         let mut databases = Vec::with_capacity(connection_strings.len());
+        let mut settings = Vec::with_capacity(connection_strings.len());
         let futures =
             connection_strings.into_iter().map(|connection_string| {
                 spawn(async move {
-                    let database: Db = Db::new(connection_string).await?;
-
-                    Ok::<_, anyhow::Error>(Arc::new(database))
+                    let name = connection_string.name.clone();
+                    (name, until(deadline, connect(connection_string)).await)
                 })
             });
 
         let database_results = try_join_all(futures).await?;
-        for database in database_results {
-            databases.push(database?)
+        for ((name, database), entry_settings) in
+            database_results.into_iter().zip(entry_settings)
+        {
+            match database {
+                Ok(database) => {
+                    settings.extend(database.iter().map(|_| entry_settings));
+                    databases.extend(database);
+                }
+                Err(err) if tolerated(&err) => {
+                    errors.write(&ErrorRecord::new(
+                        &name,
+                        ErrorStage::Connect,
+                        &err,
+                    ))?;
+                    unreachable.push(name);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        if databases.is_empty() {
+            bail!("None of the databases could be connected");
         }
 
-        Ok(Self { databases, path_to_query })
+        Ok(Self {
+            databases,
+            settings,
+            deadline,
+            path_to_query,
+            errors,
+            on_error,
+            unreachable,
+            summarize: skip_unreachable || on_error == OnError::Continue,
+        })
     }
 
-    pub async fn execute_query_from_file(&self) -> Result<()> {
-        let query = Arc::new(self.load_query_from_file().await?);
+    /// Runs the query on every database. With `OnError::Continue` a failed
+    /// database is written to the error log instead of failing the run, the
+    /// run only fails if no database succeeded. A database that is still
+    /// running at its timeout or at the deadline fails with `TimedOut`,
+    /// retries included.
+    pub async fn execute_query_from_file(&self) -> Result<Summary> {
+        let queries = self.load_queries_from_file().await?;
 
-        let futures = self.databases.iter().map(|db| {
-            let query = query.clone();
-            let db = db.clone();
+        let start = Instant::now();
+        let futures = self
+            .databases
+            .iter()
+            .zip(&self.settings)
+            .zip(queries)
+            .map(|((db, settings), query)| {
+                let db = db.clone();
+                let QuerySettings { timeout, retry } = *settings;
+                let limit = timeout
+                    .map(|timeout| (start + timeout, TimedOut::Query(timeout)))
+                    .into_iter()
+                    .chain(self.deadline)
+                    .min_by_key(|(at, _)| *at);
 
-            spawn(async move { db.query(query.as_str()).await })
-        });
+                spawn(async move {
+                    until(limit, run_query(db.as_ref(), query.as_str(), retry))
+                        .await
+                })
+            });
 
         let results = try_join_all(futures).await?;
 
-        for result in results {
-            result?;
+        for (database, result) in self.databases.iter().zip(&results) {
+            // Closing waits for the query it gave up on, dropping does not
+            match result {
+                Err(err) if err.downcast_ref::<TimedOut>().is_some() => {}
+                _ => database.close().await,
+            }
+        }
+
+        let mut summary =
+            Summary { failed: self.unreachable.clone(), ..Default::default() };
+        let mut first_error = None;
+
+        for (database, result) in self.databases.iter().zip(results) {
+            for warning in database.take_warnings() {
+                self.errors.write(&ErrorRecord::new(
+                    database.name(),
+                    ErrorStage::Decode,
+                    &anyhow!(warning),
+                ))?;
+            }
+
+            match result {
+                Ok(()) => summary.succeeded.push(database.name().to_string()),
+                Err(err) if self.on_error == OnError::Continue => {
+                    self.errors.write(&ErrorRecord::new(
+                        database.name(),
+                        ErrorStage::Query,
+                        &err,
+                    ))?;
+                    summary.failed.push(database.name().to_string());
+                }
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+
+        if let Some(err) = first_error {
+            return Err(err);
         }
 
-        Ok(())
+        if self.summarize {
+            self.errors.write_summary(&summary)?;
+        }
+        if summary.succeeded.is_empty() {
+            bail!("The query failed on every database");
+        }
+
+        Ok(summary)
+    }
+
+    /// Picks the query for every database, in the same order as
+    /// `databases`. Each one gets the most specific dialect variant of the
+    /// query file that exists, e.g. `report.mariadb.sql`, then
+    /// `report.mysql.sql`, then `report.sql`. Fails if any database is left
+    /// without a query, so nothing runs on an incomplete set of variants.
+    pub async fn load_queries_from_file(&self) -> Result<Vec<Arc<String>>> {
+        let mut loaded: HashMap<PathBuf, Arc<String>> = HashMap::new();
+        let mut queries = Vec::with_capacity(self.databases.len());
+
+        for db in &self.databases {
+            let candidates =
+                query_variants(&self.path_to_query, db.dialects());
+            let path = match candidates.iter().find(|path| path.exists()) {
+                Some(path) => path,
+                None => bail!(
+                    "No query for '{}', expected one of: {}",
+                    db.name(),
+                    candidates
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+
+            let query = match loaded.get(path) {
+                Some(query) => query.clone(),
+                None => {
+                    let query = Arc::new(load_query_from_file(path).await?);
+                    loaded.insert(path.clone(), query.clone());
+                    query
+                }
+            };
+
+            queries.push(query);
+        }
+
+        Ok(queries)
+    }
+}
+
+/// Runs `future` until `limit`, failing with its `TimedOut` past it.
+async fn until<T>(
+    limit: Option<(Instant, TimedOut)>,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match limit {
+        Some((at, timed_out)) => timeout_at(at, future)
+            .await
+            .unwrap_or_else(|_| Err(timed_out.into())),
+        None => future.await,
     }
+}
 
-    pub async fn load_query_from_file(&self) -> Result<String> {
-        let mut file = File::open(&self.path_to_query).await?;
+/// Runs `query` on `db`, retrying transient failures as long as no rows
+/// were written. Once there are, a retry would write them twice.
+async fn run_query(
+    db: &dyn Backend,
+    query: &str,
+    retry: retry::Policy,
+) -> Result<()> {
+    let mut attempt = 1;
+    loop {
+        let mut written = 0;
+        let err = match db.query(query, &mut written).await {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        let Some(delay) = retry.retry_after(attempt, &err) else {
+            return Err(err);
+        };
 
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).await?;
+        if written > 0 {
+            warn!(
+                "Not retrying the query on '{}', it already wrote {} rows: \
+                 {:#}",
+                db.name(),
+                written,
+                err
+            );
+            return Err(err);
+        }
 
-        Ok(contents)
+        warn!(
+            "Query on '{}' failed, retrying in {:?}: {:#}",
+            db.name(),
+            delay,
+            err
+        );
+        sleep(delay).await;
+        attempt += 1;
     }
 }
+
+/// `report.sql` with dialects `["mariadb", "mysql"]` gives
+/// `report.mariadb.sql`, `report.mysql.sql` and `report.sql`.
+fn query_variants(path: &Path, dialects: &[&str]) -> Vec<PathBuf> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    let mut variants: Vec<PathBuf> = dialects
+        .iter()
+        .map(|dialect| {
+            let file_name = match path.extension() {
+                Some(extension) => format!(
+                    "{}.{}.{}",
+                    stem,
+                    dialect,
+                    extension.to_string_lossy()
+                ),
+                None => format!("{}.{}", stem, dialect),
+            };
+
+            path.with_file_name(file_name)
+        })
+        .collect();
+
+    variants.push(path.to_path_buf());
+    variants
+}
+
+async fn load_query_from_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).await?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await?;
+
+    Ok(contents)
+}
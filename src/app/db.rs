@@ -1,162 +1,507 @@
-use std::collections::HashMap;
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
 
-use anyhow::Result;
-use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use futures::stream::BoxStream;
 use serde_json::Value;
-use serde_json::json;
-use serde_json::to_string;
 use sqlx::Postgres;
-use sqlx::types::BigDecimal;
-use sqlx::types::JsonValue;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
 use tokio_stream::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::warn;
+use url::Url;
 
-use sqlx::postgres::{PgPool, PgRow};
-use sqlx::{Column, Row, ValueRef};
+use sqlx::postgres::{PgConnectOptions, PgPool, PgRow};
+use sqlx::{Column, Executor, Row, Transaction, TypeInfo};
 
-use crate::ConnectionString;
+use crate::{
+    Backend, ColumnDescription, ColumnKeys, ConnectionString, EndpointPolicy,
+    JsonRow, PoolSettings,
+    decoders::{Formats, RowDecoder},
+    pool,
+    relay::Relay,
+    retry, ssh, targets, tls,
+};
+
+/// Rows buffered between a tenant schema query and its consumer.
+const SCHEMA_ROW_BUFFER: usize = 64;
+
+/// Default size of the pool tenant schemas share.
+const SCHEMA_POOL_SIZE: u32 = 10;
 
 pub struct Db {
     pub name: String,
-    db: PgPool,
+    pool: Pool,
+    /// Tenant schema the query runs in, set for targets made by
+    /// `Db::connect_schemas`.
+    schema: Option<String>,
+    formats: Formats,
+    /// Columns the queries wrote as null for failing to decode, until they
+    /// are reported.
+    warnings: Mutex<Vec<String>>,
 }
 
-impl Db {
-    pub async fn new(
-        ConnectionString { name, uri }: ConnectionString,
-    ) -> Result<Self> {
-        let db = PgPool::connect(&uri).await?;
+/// Aborts the task it holds when dropped, so a query that is no longer read
+/// from, e.g. after a timeout, gives its connection back.
+struct AbortOnDrop(JoinHandle<()>);
 
-        Ok(Self { db, name })
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
     }
+}
 
-    fn row_to_json(&self, row: PgRow) -> serde_json::Map<String, Value> {
-        let mut json_obj = serde_json::Map::new();
-        let mut key_count: HashMap<String, usize> = HashMap::new();
+/// A connection pool and what has to outlive it.
+#[derive(Clone)]
+pub struct Pool {
+    pub db: PgPool,
+    /// Kept alive for as long as the pool connects through it.
+    _relay: Option<Arc<Relay>>,
+    /// The one of `endpoints` that was picked, reported with every row.
+    endpoint: Option<String>,
+}
 
-        json_obj
-            .insert("db_name".to_string(), Value::String(self.name.clone()));
+impl Db {
+    pub async fn new(connection_string: ConnectionString) -> Result<Self> {
+        let pool = Self::connect_pool(&connection_string).await?;
+
+        Ok(Self {
+            pool,
+            formats: Formats::from(&connection_string),
+            name: connection_string.name,
+            schema: None,
+            warnings: Mutex::default(),
+        })
+    }
+
+    /// Connects to the URI of the entry, or to one of its `endpoints` as
+    /// the `endpoint_policy` allows. Transient failures are retried as the
+    /// `retry` settings of the entry say.
+    pub async fn connect_pool(
+        connection_string: &ConnectionString,
+    ) -> Result<Pool> {
+        let retry = retry::Policy::new(connection_string.retry.as_ref())?;
 
-        for col in row.columns() {
-            let name = col.name();
-            let count = key_count
-                .entry(name.to_string())
-                .and_modify(|c| *c += 1)
-                .or_insert(0);
-            let key = if *count == 0 {
-                name
+        let mut attempt = 1;
+        loop {
+            let result = if connection_string.endpoints.is_empty() {
+                Self::connect_uri(connection_string, &connection_string.uri)
+                    .await
             } else {
-                &format!("{}_{}", name, count)
+                Self::connect_endpoints(connection_string).await
             };
 
-            let json_value: Value = if let Ok(Some(v)) =
-                row.try_get::<Option<i16>, _>(name)
-            {
-                json!(v)
-            } else if let Ok(Some(v)) = row.try_get::<Option<i32>, _>(name) {
-                json!(v)
-            } else if let Ok(Some(v)) = row.try_get::<Option<i64>, _>(name) {
-                json!(v)
-            } else if let Ok(Some(v)) = row.try_get::<Option<f32>, _>(name) {
-                json!(v)
-            } else if let Ok(Some(v)) = row.try_get::<Option<f64>, _>(name) {
-                json!(v)
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<BigDecimal>, _>(name)
-            {
-                match v.to_string().parse::<f64>() {
-                    Ok(num) => json!(num),
-                    Err(_) => Value::String(v.to_string()),
-                }
-            } else if let Ok(Some(v)) = row.try_get::<Option<bool>, _>(name) {
-                json!(v)
-            } else if let Ok(Some(v)) = row.try_get::<Option<String>, _>(name)
-            {
-                Value::String(v)
-            } else if let Ok(Some(v)) = row.try_get::<Option<&str>, _>(name) {
-                Value::String(v.to_string())
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<NaiveDateTime>, _>(name)
-            {
-                json!(v)
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<NaiveDate>, _>(name)
-            {
-                json!(v)
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<NaiveTime>, _>(name)
-            {
-                json!(v)
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<DateTime<Utc>>, _>(name)
-            {
-                json!(v)
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<JsonValue>, _>(name)
-            {
-                v
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<Vec<String>>, _>(name)
-            {
-                json!(v)
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<Vec<i32>>, _>(name)
-            {
-                json!(v)
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<Vec<i64>>, _>(name)
-            {
-                json!(v)
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<Vec<f64>>, _>(name)
-            {
-                json!(v)
-            } else if let Ok(Some(v)) =
-                row.try_get::<Option<Vec<bool>>, _>(name)
-            {
-                json!(v)
-            } else if let Ok(None) = row.try_get::<Option<i16>, _>(name) {
-                Value::Null
-            } else if let Ok(None) = row.try_get::<Option<i32>, _>(name) {
-                Value::Null
-            } else if let Ok(None) = row.try_get::<Option<i64>, _>(name) {
-                Value::Null
-            } else if let Ok(None) = row.try_get::<Option<f32>, _>(name) {
-                Value::Null
-            } else if let Ok(None) = row.try_get::<Option<f64>, _>(name) {
-                Value::Null
-            } else if let Ok(None) = row.try_get::<Option<BigDecimal>, _>(name)
-            {
-                Value::Null
-            } else if let Ok(None) = row.try_get::<Option<bool>, _>(name) {
-                Value::Null
-            } else if let Ok(None) = row.try_get::<Option<String>, _>(name) {
-                Value::Null
-            } else {
-                // For enums and other types, try to decode as string using database-specific Decode
-                match row.try_get_raw(name) {
-                    Ok(raw) if !raw.is_null() => {
-                        match <String as sqlx::Decode<Postgres>>::decode(raw) {
-                            Ok(s) => Value::String(s),
-                            Err(_) => Value::Null,
+            let err = match result {
+                Ok(pool) => return Ok(pool),
+                Err(err) => err,
+            };
+            let Some(delay) = retry.retry_after(attempt, &err) else {
+                return Err(err);
+            };
+
+            warn!(
+                "Connecting to '{}' failed, retrying in {:?}: {:#}",
+                connection_string.name, delay, err
+            );
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Goes through the endpoints in order, skipping the ones that fail to
+    /// connect. Replicas are told apart by `pg_is_in_recovery()`.
+    async fn connect_endpoints(
+        connection_string: &ConnectionString,
+    ) -> Result<Pool> {
+        let policy = connection_string.endpoint_policy.unwrap_or_default();
+        let mut primary: Option<Pool> = None;
+        let mut errors = Vec::new();
+        // Makes the whole walk worth a retry
+        let mut transient = None;
+
+        for endpoint in &connection_string.endpoints {
+            let label = targets::redact_password(endpoint);
+
+            let pool =
+                match Self::connect_uri(connection_string, endpoint).await {
+                    Ok(pool) => Pool { endpoint: Some(label.clone()), ..pool },
+                    Err(err) => {
+                        errors.push(format!("{}: {:#}", label, err));
+                        if transient.is_none() && retry::is_transient(&err) {
+                            transient = Some(err);
                         }
+                        continue;
                     }
-                    _ => Value::Null,
+                };
+            if policy == EndpointPolicy::Any {
+                return Ok(pool);
+            }
+
+            let in_recovery: bool =
+                match sqlx::query_scalar("SELECT pg_is_in_recovery()")
+                    .fetch_one(&pool.db)
+                    .await
+                {
+                    Ok(in_recovery) => in_recovery,
+                    Err(err) => {
+                        errors.push(format!("{}: {:#}", label, err));
+                        pool.db.close().await;
+                        continue;
+                    }
+                };
+
+            match (policy, in_recovery) {
+                (EndpointPolicy::PreferReplica, true) => {
+                    if let Some(primary) = primary.take() {
+                        primary.db.close().await;
+                    }
+                    return Ok(pool);
                 }
+                (EndpointPolicy::PreferReplica, false)
+                    if primary.is_none() =>
+                {
+                    primary = Some(pool);
+                }
+                (EndpointPolicy::PrimaryOnly, false) => return Ok(pool),
+                (EndpointPolicy::PrimaryOnly, true) => {
+                    errors.push(format!("{}: is a replica", label));
+                    pool.db.close().await;
+                }
+                _ => pool.db.close().await,
+            }
+        }
+
+        if let Some(primary) = primary {
+            return Ok(primary);
+        }
+
+        let message = format!(
+            "No endpoint of '{}' could be used:\n  {}",
+            connection_string.name,
+            errors.join("\n  ")
+        );
+        match transient {
+            Some(err) => Err(err.context(message)),
+            None => Err(anyhow!(message)),
+        }
+    }
+
+    /// Connects to `uri` with the `tls` block of the entry on top. Entries
+    /// with an `ssh` bastion or a TLS `server_name` connect through a relay.
+    /// The `timeout` of the entry becomes the `statement_timeout`.
+    async fn connect_uri(
+        ConnectionString { name, tls, ssh, pool: settings, timeout, .. }: &ConnectionString,
+        uri: &str,
+    ) -> Result<Pool> {
+        let mut options = PgConnectOptions::from_str(uri)?;
+        if let Some(tls) = tls {
+            options = tls::apply(options, tls)?;
+        }
+        if let Some(timeout) = timeout {
+            // In whole milliseconds, rounded up as 0 turns it off
+            let millis = (timeout * 1000.0).ceil().max(1.0) as u64;
+            options = options
+                .options([("statement_timeout", format!("{}ms", millis))]);
+        }
+
+        let server_name =
+            tls.as_ref().and_then(|tls| tls.server_name.as_deref());
+        let relay = if ssh.is_some() || server_name.is_some() {
+            if options.get_socket().is_some()
+                || options.get_host().starts_with('/')
+            {
+                bail!("'{}' needs a TCP host to connect through", name);
+            }
+
+            let tunnel = match ssh {
+                Some(ssh) => Some(ssh::open(ssh).await?),
+                None => None,
             };
+            let relay =
+                Relay::start(options.get_host(), options.get_port(), tunnel)
+                    .await?;
+
+            options = options.socket(relay.directory());
+            if let Some(server_name) = server_name {
+                options = options.host(server_name);
+            }
+
+            Some(Arc::new(relay))
+        } else {
+            None
+        };
+
+        let db = pool::connect(
+            settings.as_ref(),
+            pool::DEFAULT_MAX_CONNECTIONS,
+            options,
+        )
+        .await?;
 
-            json_obj.insert(key.to_string(), json_value);
+        Ok(Pool { db, _relay: relay, endpoint: None })
+    }
+
+    /// `postgres://host/app?schemas=tenant_*&exclude_schemas=tenant_demo`
+    /// becomes one target per matching schema. They all share one pool and
+    /// run the query with `search_path` set to their schema. Without a
+    /// `schemas` pattern this is the same as `Db::new`.
+    pub async fn connect_schemas(
+        connection_string: ConnectionString,
+    ) -> Result<Vec<Self>> {
+        let ConnectionString { name, uri, .. } = &connection_string;
+        let mut url = Url::parse(uri)
+            .with_context(|| format!("Invalid Postgres URI for '{}'", name))?;
+
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        let mut params = Vec::new();
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "schemas" => include.push(value.to_string()),
+                "exclude_schemas" => exclude.push(value.to_string()),
+                _ => params.push((key.to_string(), value.to_string())),
+            }
+        }
+
+        if include.is_empty() {
+            return Ok(vec![Self::new(connection_string).await?]);
+        }
+        let include = targets::compile_patterns(&include)?;
+        let exclude = targets::compile_patterns(&exclude)?;
+
+        url.set_query(None);
+        if !params.is_empty() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+
+        // Shared by all schemas, so it gets more than one connection
+        let settings = connection_string.pool.clone().unwrap_or_default();
+        let pool = Self::connect_pool(&ConnectionString {
+            uri: url.to_string(),
+            pool: Some(PoolSettings {
+                max_connections: settings
+                    .max_connections
+                    .or(Some(SCHEMA_POOL_SIZE)),
+                ..settings
+            }),
+            ..connection_string.clone()
+        })
+        .await?;
+
+        let schemas: Vec<String> = sqlx::query_scalar(
+            "SELECT nspname FROM pg_namespace \
+             WHERE nspname NOT LIKE 'pg\\_%' \
+             AND nspname <> 'information_schema' \
+             ORDER BY nspname",
+        )
+        .fetch_all(&pool.db)
+        .await?;
+
+        let targets: Vec<Self> = schemas
+            .into_iter()
+            .filter(|schema| {
+                include.iter().any(|pattern| pattern.matches(schema))
+                    && !exclude.iter().any(|pattern| pattern.matches(schema))
+            })
+            .map(|schema| Self {
+                name: name.clone(),
+                pool: pool.clone(),
+                schema: Some(schema),
+                formats: Formats::from(&connection_string),
+                warnings: Mutex::default(),
+            })
+            .collect();
+
+        if targets.is_empty() {
+            pool.db.close().await;
+            bail!("No schemas match for '{}'", name);
+        }
+
+        Ok(targets)
+    }
+
+    /// Runs `query` in a transaction with `search_path` set to `schema`.
+    /// The transaction borrows its connection, so it lives in a task of its
+    /// own that hands the rows over through a channel. Dropping the stream
+    /// aborts the task.
+    fn fetch_in_schema<'a>(
+        &'a self,
+        schema: &str,
+        query: &str,
+    ) -> BoxStream<'a, Result<JsonRow>> {
+        let (sender, receiver) = mpsc::channel(SCHEMA_ROW_BUFFER);
+        let pool = self.pool.db.clone();
+        let schema = schema.to_string();
+        let query = query.to_string();
+
+        let task = AbortOnDrop(tokio::spawn(async move {
+            let result: Result<()> = async {
+                let mut tx = pool.begin().await?;
+                set_search_path(&mut tx, &schema).await?;
+
+                let mut rows = sqlx::query(&query).fetch(&mut *tx);
+                while let Some(row) = rows.next().await {
+                    if sender.send(Ok(row?)).await.is_err() {
+                        return Ok(());
+                    }
+                }
+                drop(rows);
+
+                tx.commit().await?;
+                Ok(())
+            }
+            .await;
+
+            if let Err(err) = result {
+                let _ = sender.send(Err(err)).await;
+            }
+        }));
+
+        let mut columns = None;
+        Box::pin(ReceiverStream::new(receiver).map(
+            move |row: Result<PgRow>| {
+                // The task lives as long as the stream
+                let _ = &task;
+                let row = row?;
+                let columns = columns.get_or_insert_with(|| {
+                    ResultColumns::new(&row, self.formats)
+                });
+                Ok(self.row_to_json(&row, columns))
+            },
+        ))
+    }
+
+    fn row_to_json(
+        &self,
+        row: &PgRow,
+        columns: &mut ResultColumns,
+    ) -> JsonRow {
+        let mut json_obj = serde_json::Map::new();
+
+        json_obj
+            .insert("db_name".to_string(), Value::String(self.name.clone()));
+        if let Some(schema) = &self.schema {
+            json_obj.insert(
+                "schema_name".to_string(),
+                Value::String(schema.clone()),
+            );
+        }
+        if let Some(endpoint) = &self.pool.endpoint {
+            json_obj.insert(
+                "endpoint".to_string(),
+                Value::String(endpoint.clone()),
+            );
+        }
+
+        for (index, key) in columns.keys.iter().enumerate() {
+            json_obj.insert(key.clone(), columns.decoder.decode(row, index));
         }
+        self.warnings
+            .lock()
+            .expect("warnings lock poisoned")
+            .extend(columns.decoder.take_warnings());
 
         json_obj
     }
+}
+
+/// JSON keys and decoders of the columns of a result set, worked out once
+/// from its first row instead of for every row.
+struct ResultColumns {
+    keys: Vec<String>,
+    decoder: RowDecoder,
+}
+
+impl ResultColumns {
+    fn new(row: &PgRow, formats: Formats) -> Self {
+        let mut keys = ColumnKeys::default();
+
+        Self {
+            keys: row
+                .columns()
+                .iter()
+                .map(|column| keys.next(column.name()))
+                .collect(),
+            decoder: RowDecoder::new(row, formats),
+        }
+    }
+}
+
+/// Scoped to the transaction, so the pooled connection is clean afterwards.
+async fn set_search_path(
+    tx: &mut Transaction<'_, Postgres>,
+    schema: &str,
+) -> Result<()> {
+    sqlx::query("SELECT set_config('search_path', quote_ident($1), true)")
+        .bind(schema)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+#[async_trait]
+impl Backend for Db {
+    async fn connect(connection_string: ConnectionString) -> Result<Self> {
+        Self::new(connection_string).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dialects(&self) -> &'static [&'static str] {
+        &["postgres"]
+    }
 
-    pub async fn query(&self, query: &str) -> Result<()> {
-        let mut rows = sqlx::query(query).fetch(&self.db);
-        while let Some(row) = rows.try_next().await? {
-            let json = self.row_to_json(row);
-            println!("{}", to_string(&json)?)
+    fn fetch<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<JsonRow>> {
+        if let Some(schema) = &self.schema {
+            return self.fetch_in_schema(schema, query);
         }
 
-        Ok(())
+        let mut columns = None;
+        Box::pin(sqlx::query(query).fetch(&self.pool.db).map(move |row| {
+            let row = row?;
+            let columns = columns
+                .get_or_insert_with(|| ResultColumns::new(&row, self.formats));
+            Ok(self.row_to_json(&row, columns))
+        }))
+    }
+
+    async fn describe(&self, query: &str) -> Result<Vec<ColumnDescription>> {
+        let description = match &self.schema {
+            Some(schema) => {
+                let mut tx = self.pool.db.begin().await?;
+                set_search_path(&mut tx, schema).await?;
+                let description = (&mut *tx).describe(query).await?;
+                tx.rollback().await?;
+                description
+            }
+            None => self.pool.db.describe(query).await?,
+        };
+
+        Ok(description
+            .columns()
+            .iter()
+            .map(|column| ColumnDescription {
+                name: column.name().to_string(),
+                type_name: column.type_info().name().to_string(),
+            })
+            .collect())
+    }
+
+    async fn close(&self) {
+        self.pool.db.close().await
+    }
+
+    fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(
+            &mut self.warnings.lock().expect("warnings lock poisoned"),
+        )
     }
 }
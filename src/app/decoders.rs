@@ -0,0 +1,951 @@
+use std::fmt::{self, Display, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::Serialize;
+use serde_json::{Number, Value};
+use sqlx::error::BoxDynError;
+use sqlx::postgres::types::Oid;
+use sqlx::postgres::{
+    PgRow, PgTypeInfo, PgTypeKind, PgValueFormat, PgValueRef,
+};
+use sqlx::types::JsonValue;
+use sqlx::{Column, Decode, Postgres, Row, Type, TypeInfo, ValueRef};
+
+use crate::{ByteaEncoding, ConnectionString, InetOutput, NumericOutput};
+
+/// Turns one non-null cell into JSON.
+pub type Decoder = fn(PgValueRef<'_>, &Formats) -> Result<Value, BoxDynError>;
+
+/// How values that have more than one way to be written are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Formats {
+    pub numeric: NumericOutput,
+    pub inet: InetOutput,
+    pub bytea: ByteaEncoding,
+    /// bytea values longer than this are cut short, with a marker.
+    pub bytea_max_bytes: Option<usize>,
+}
+
+impl From<&ConnectionString> for Formats {
+    fn from(connection_string: &ConnectionString) -> Self {
+        let bytea = connection_string.bytea.clone().unwrap_or_default();
+
+        Self {
+            numeric: connection_string.numeric.unwrap_or_default(),
+            inet: connection_string.inet.unwrap_or_default(),
+            bytea: bytea.encoding.unwrap_or_default(),
+            bytea_max_bytes: bytea.max_bytes,
+        }
+    }
+}
+
+/// Built-in type OIDs, the same in every database.
+mod oid {
+    pub const BOOL: u32 = 16;
+    pub const CHAR: u32 = 18;
+    pub const BYTEA: u32 = 17;
+    pub const NAME: u32 = 19;
+    pub const INT8: u32 = 20;
+    pub const INT2: u32 = 21;
+    pub const INT4: u32 = 23;
+    pub const TEXT: u32 = 25;
+    pub const OID: u32 = 26;
+    pub const JSON: u32 = 114;
+    pub const XML: u32 = 142;
+    pub const CIDR: u32 = 650;
+    pub const MACADDR8: u32 = 774;
+    pub const MACADDR: u32 = 829;
+    pub const INET: u32 = 869;
+    pub const FLOAT4: u32 = 700;
+    pub const FLOAT8: u32 = 701;
+    pub const UNKNOWN: u32 = 705;
+    pub const MONEY: u32 = 790;
+    pub const BPCHAR: u32 = 1042;
+    pub const VARCHAR: u32 = 1043;
+    pub const DATE: u32 = 1082;
+    pub const TIME: u32 = 1083;
+    pub const TIMESTAMP: u32 = 1114;
+    pub const TIMESTAMPTZ: u32 = 1184;
+    pub const INTERVAL: u32 = 1186;
+    pub const TIMETZ: u32 = 1266;
+    pub const NUMERIC: u32 = 1700;
+    pub const REFCURSOR: u32 = 1790;
+    pub const UUID: u32 = 2950;
+    pub const JSONB: u32 = 3802;
+}
+
+/// The decoders of one result set, one per column, resolved from the types
+/// of its first row.
+pub struct RowDecoder {
+    columns: Vec<ColumnDecoder>,
+    formats: Formats,
+    /// Columns that started writing nulls since the last `take_warnings`.
+    warnings: Vec<String>,
+}
+
+struct ColumnDecoder {
+    name: String,
+    decoder: Decoder,
+    /// A failed cell is reported once per column, not for every row.
+    failed: bool,
+}
+
+impl RowDecoder {
+    pub fn new(row: &PgRow, formats: Formats) -> Self {
+        let columns = row
+            .columns()
+            .iter()
+            .map(|column| ColumnDecoder {
+                name: column.name().to_string(),
+                decoder: decoder_for(column.type_info()),
+                failed: false,
+            })
+            .collect();
+
+        Self { columns, formats, warnings: Vec::new() }
+    }
+
+    /// Decodes the cell at `index`. Cells that fail to decode are written as
+    /// null, with a warning for the first one of each column.
+    pub fn decode(&mut self, row: &PgRow, index: usize) -> Value {
+        let column = &mut self.columns[index];
+        let result = row
+            .try_get_raw(index)
+            .map_err(BoxDynError::from)
+            .and_then(|raw| match raw.is_null() {
+                true => Ok(Value::Null),
+                false => (column.decoder)(raw, &self.formats),
+            });
+
+        match result {
+            Ok(value) => value,
+            Err(err) => {
+                if !column.failed {
+                    column.failed = true;
+                    self.warnings.push(format!(
+                        "Column '{}' is written as null, it failed to \
+                         decode: {}",
+                        column.name, err
+                    ));
+                }
+                Value::Null
+            }
+        }
+    }
+
+    /// The warnings of the columns that failed since the last call.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+/// Picks the decoder for a column type by its OID. Domains are decoded as
+/// their base type, and types without a decoder fail with their name.
+pub fn decoder_for(type_info: &PgTypeInfo) -> Decoder {
+    if let PgTypeKind::Domain(base) = type_info.kind() {
+        return decoder_for(base);
+    }
+    let Some(type_oid) = type_info.oid() else {
+        return unsupported;
+    };
+
+    match type_oid.0 {
+        oid::BOOL => json::<bool>,
+        oid::INT2 => json::<i16>,
+        oid::INT4 => json::<i32>,
+        oid::INT8 => json::<i64>,
+        oid::OID => object_id,
+        oid::FLOAT4 => float4,
+        oid::FLOAT8 => json::<f64>,
+        oid::NUMERIC => numeric,
+        oid::MONEY => money,
+        oid::BYTEA => bytea,
+        // Their binary form is their text as well
+        oid::CHAR
+        | oid::NAME
+        | oid::TEXT
+        | oid::XML
+        | oid::UNKNOWN
+        | oid::BPCHAR
+        | oid::VARCHAR
+        | oid::REFCURSOR => text,
+        oid::JSON | oid::JSONB => json_document,
+        oid::DATE => json::<NaiveDate>,
+        oid::TIME => json::<NaiveTime>,
+        oid::TIMETZ => json::<TimeTzText>,
+        oid::TIMESTAMP => json::<NaiveDateTime>,
+        oid::TIMESTAMPTZ => json::<DateTime<Utc>>,
+        oid::INTERVAL => json::<IntervalText>,
+        oid::UUID => json::<UuidText>,
+        oid::INET | oid::CIDR => inet,
+        oid::MACADDR | oid::MACADDR8 => json::<MacAddrText>,
+        _ => match type_info.kind() {
+            PgTypeKind::Array(element) => array_decoder_for(element),
+            _ if is_textual(type_info) => text,
+            _ => unsupported,
+        },
+    }
+}
+
+/// Enums and `citext` are sent as text. Other types created in the
+/// database, such as hstore, ltree or PostGIS geometries, have binary forms
+/// of their own and are left unsupported.
+fn is_textual(type_info: &PgTypeInfo) -> bool {
+    match type_info.kind() {
+        PgTypeKind::Enum(_) => true,
+        PgTypeKind::Simple => type_info.name() == "citext",
+        _ => false,
+    }
+}
+
+/// Arrays decode their elements like the scalar decoders do, with null
+/// elements kept as null.
+fn array_decoder_for(element: &PgTypeInfo) -> Decoder {
+    let Some(element_oid) = element.oid() else {
+        return unsupported;
+    };
+
+    match element_oid.0 {
+        oid::BOOL => json::<Vec<Option<bool>>>,
+        oid::INT2 => json::<Vec<Option<i16>>>,
+        oid::INT4 => json::<Vec<Option<i32>>>,
+        oid::INT8 => json::<Vec<Option<i64>>>,
+        oid::FLOAT4 => float4_array,
+        oid::FLOAT8 => json::<Vec<Option<f64>>>,
+        oid::NUMERIC => numeric_array,
+        oid::MONEY => money_array,
+        oid::BYTEA => bytea_array,
+        oid::CHAR
+        | oid::NAME
+        | oid::TEXT
+        | oid::XML
+        | oid::UNKNOWN
+        | oid::BPCHAR
+        | oid::VARCHAR
+        | oid::REFCURSOR => json::<Vec<Option<String>>>,
+        oid::JSON | oid::JSONB => json_document_array,
+        oid::DATE => json::<Vec<Option<NaiveDate>>>,
+        oid::TIME => json::<Vec<Option<NaiveTime>>>,
+        oid::TIMETZ => json::<Vec<Option<TimeTzText>>>,
+        oid::TIMESTAMP => json::<Vec<Option<NaiveDateTime>>>,
+        oid::TIMESTAMPTZ => json::<Vec<Option<DateTime<Utc>>>>,
+        oid::INTERVAL => json::<Vec<Option<IntervalText>>>,
+        oid::UUID => json::<Vec<Option<UuidText>>>,
+        oid::INET | oid::CIDR => inet_array,
+        oid::MACADDR | oid::MACADDR8 => json::<Vec<Option<MacAddrText>>>,
+        _ if is_textual(element) => json::<Vec<Option<String>>>,
+        _ => unsupported,
+    }
+}
+
+fn json<T>(value: PgValueRef<'_>, _: &Formats) -> Result<Value, BoxDynError>
+where
+    T: for<'r> Decode<'r, Postgres> + Serialize,
+{
+    Ok(serde_json::to_value(T::decode(value)?)?)
+}
+
+fn json_document(
+    value: PgValueRef<'_>,
+    _: &Formats,
+) -> Result<Value, BoxDynError> {
+    Ok(round_numbers(<JsonValue as Decode<Postgres>>::decode(value)?))
+}
+
+fn json_document_array(
+    value: PgValueRef<'_>,
+    _: &Formats,
+) -> Result<Value, BoxDynError> {
+    let values = <Vec<Option<JsonValue>> as Decode<Postgres>>::decode(value)?;
+    Ok(Value::Array(
+        values
+            .into_iter()
+            .map(|value| value.map_or(Value::Null, round_numbers))
+            .collect(),
+    ))
+}
+
+fn text(value: PgValueRef<'_>, _: &Formats) -> Result<Value, BoxDynError> {
+    Ok(Value::String(<&str as Decode<Postgres>>::decode(value)?.to_string()))
+}
+
+fn object_id(
+    value: PgValueRef<'_>,
+    _: &Formats,
+) -> Result<Value, BoxDynError> {
+    Ok(Value::from(<Oid as Decode<Postgres>>::decode(value)?.0))
+}
+
+fn float4(value: PgValueRef<'_>, _: &Formats) -> Result<Value, BoxDynError> {
+    Ok(float4_to_json(<f32 as Decode<Postgres>>::decode(value)?))
+}
+
+fn float4_array(
+    value: PgValueRef<'_>,
+    _: &Formats,
+) -> Result<Value, BoxDynError> {
+    let values = <Vec<Option<f32>> as Decode<Postgres>>::decode(value)?;
+    Ok(Value::Array(
+        values
+            .into_iter()
+            .map(|value| value.map_or(Value::Null, float4_to_json))
+            .collect(),
+    ))
+}
+
+fn numeric(
+    value: PgValueRef<'_>,
+    formats: &Formats,
+) -> Result<Value, BoxDynError> {
+    let NumericText(text) = NumericText::decode(value)?;
+    Ok(numeric_to_json(text, formats.numeric))
+}
+
+fn numeric_array(
+    value: PgValueRef<'_>,
+    formats: &Formats,
+) -> Result<Value, BoxDynError> {
+    let values =
+        <Vec<Option<NumericText>> as Decode<Postgres>>::decode(value)?;
+    Ok(Value::Array(
+        values
+            .into_iter()
+            .map(|value| {
+                value.map_or(Value::Null, |NumericText(text)| {
+                    numeric_to_json(text, formats.numeric)
+                })
+            })
+            .collect(),
+    ))
+}
+
+fn money(
+    value: PgValueRef<'_>,
+    formats: &Formats,
+) -> Result<Value, BoxDynError> {
+    let MoneyText(text) = MoneyText::decode(value)?;
+    Ok(numeric_to_json(text, formats.numeric))
+}
+
+fn money_array(
+    value: PgValueRef<'_>,
+    formats: &Formats,
+) -> Result<Value, BoxDynError> {
+    let values = <Vec<Option<MoneyText>> as Decode<Postgres>>::decode(value)?;
+    Ok(Value::Array(
+        values
+            .into_iter()
+            .map(|value| {
+                value.map_or(Value::Null, |MoneyText(text)| {
+                    numeric_to_json(text, formats.numeric)
+                })
+            })
+            .collect(),
+    ))
+}
+
+fn bytea(
+    value: PgValueRef<'_>,
+    formats: &Formats,
+) -> Result<Value, BoxDynError> {
+    let bytes = <&[u8] as Decode<Postgres>>::decode(value)?;
+    Ok(bytes_to_json(bytes, formats))
+}
+
+fn bytea_array(
+    value: PgValueRef<'_>,
+    formats: &Formats,
+) -> Result<Value, BoxDynError> {
+    let values = <Vec<Option<Vec<u8>>> as Decode<Postgres>>::decode(value)?;
+    Ok(Value::Array(
+        values
+            .into_iter()
+            .map(|value| {
+                value.map_or(Value::Null, |bytes| {
+                    bytes_to_json(&bytes, formats)
+                })
+            })
+            .collect(),
+    ))
+}
+
+fn inet(
+    value: PgValueRef<'_>,
+    formats: &Formats,
+) -> Result<Value, BoxDynError> {
+    Ok(Inet::decode(value)?.to_json(formats.inet))
+}
+
+fn inet_array(
+    value: PgValueRef<'_>,
+    formats: &Formats,
+) -> Result<Value, BoxDynError> {
+    let values = <Vec<Option<Inet>> as Decode<Postgres>>::decode(value)?;
+    Ok(Value::Array(
+        values
+            .into_iter()
+            .map(|value| {
+                value.map_or(Value::Null, |inet| inet.to_json(formats.inet))
+            })
+            .collect(),
+    ))
+}
+
+fn unsupported(
+    value: PgValueRef<'_>,
+    _: &Formats,
+) -> Result<Value, BoxDynError> {
+    let type_info = value.type_info();
+    Err(format!(
+        "no decoder for type {} (OID {})",
+        type_info.name(),
+        type_info
+            .oid()
+            .map_or_else(|| "unknown".to_string(), |oid| oid.0.to_string())
+    )
+    .into())
+}
+
+/// Goes through the shortest decimal form, so a REAL of 1.1 stays 1.1
+/// instead of widening to 1.100000023841858. NaN and infinities are null.
+fn float4_to_json(value: f32) -> Value {
+    value
+        .to_string()
+        .parse()
+        .ok()
+        .and_then(Number::from_f64)
+        .map_or(Value::Null, Value::Number)
+}
+
+/// Writes the decimal `text` of a NUMERIC or DECIMAL as `numeric` says.
+/// NaN and the infinities are no JSON numbers, they are always strings.
+pub fn numeric_to_json(text: String, numeric: NumericOutput) -> Value {
+    let number = match numeric {
+        NumericOutput::String => None,
+        NumericOutput::Exact => text.parse().ok(),
+        NumericOutput::Float => text.parse().ok().and_then(Number::from_f64),
+    };
+
+    number.map_or(Value::String(text), Value::Number)
+}
+
+/// serde_json keeps the digits of the numbers it parses, which
+/// `NumericOutput::Exact` relies on. Numbers inside JSON documents are read
+/// back as integers or doubles instead, so that only NUMERIC and DECIMAL
+/// columns and integers too wide for 64 bits are written with their digits
+/// as they came.
+pub fn round_numbers(value: Value) -> Value {
+    match value {
+        Value::Number(number) => Value::Number(round_number(number)),
+        Value::Array(values) => {
+            Value::Array(values.into_iter().map(round_numbers).collect())
+        }
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, round_numbers(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+fn round_number(number: Number) -> Number {
+    if let Some(v) = number.as_i64() {
+        Number::from(v)
+    } else if let Some(v) = number.as_u64() {
+        Number::from(v)
+    } else {
+        number.as_f64().and_then(Number::from_f64).unwrap_or(number)
+    }
+}
+
+/// How the numbers of a column are written, for backends that hand rows over
+/// as JSON with every digit of their numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberKind {
+    /// DECIMAL with this many fractional digits, written as `--numeric`
+    /// says.
+    Decimal(usize),
+    /// Integers wider than 64 bits, like DuckDB's HUGEINT, kept exact unless
+    /// `--numeric` asks for strings.
+    Wide,
+    /// Anything else, read as integers or doubles.
+    Plain,
+}
+
+/// Writes `value`, or the elements of a list of them, from a column of
+/// `kind`.
+pub fn number_to_json(
+    value: Value,
+    kind: NumberKind,
+    numeric: NumericOutput,
+) -> Value {
+    match (value, kind) {
+        (Value::Number(number), NumberKind::Decimal(scale)) => {
+            numeric_to_json(decimal_text(&number, scale), numeric)
+        }
+        (Value::Number(number), NumberKind::Wide) => match numeric {
+            NumericOutput::String => Value::String(number.to_string()),
+            NumericOutput::Exact | NumericOutput::Float => {
+                Value::Number(number)
+            }
+        },
+        (Value::Array(values), NumberKind::Decimal(_) | NumberKind::Wide) => {
+            Value::Array(
+                values
+                    .into_iter()
+                    .map(|value| number_to_json(value, kind, numeric))
+                    .collect(),
+            )
+        }
+        (value, _) => round_numbers(value),
+    }
+}
+
+/// The digits of a DECIMAL `number` with `scale` fractional digits. Narrow
+/// decimals sent as doubles lose their trailing zeros, which are put back.
+fn decimal_text(number: &Number, scale: usize) -> String {
+    let mut text = number.to_string();
+    let fraction = text.split_once('.').map_or(0, |(_, f)| f.len());
+
+    if text.contains(['e', 'E']) || fraction > scale {
+        return format!("{:.*}", scale, number.as_f64().unwrap_or_default());
+    }
+    if fraction == 0 && scale > 0 {
+        text.push('.');
+    }
+    text.extend(std::iter::repeat_n('0', scale - fraction));
+
+    text
+}
+
+/// Encodes `bytes` as `formats` say. Values cut short at `bytea_max_bytes`
+/// end in `...(<length> bytes)`, with the length of the whole value.
+pub fn bytes_to_json(bytes: &[u8], formats: &Formats) -> Value {
+    let shown = match formats.bytea_max_bytes {
+        Some(max_bytes) if bytes.len() > max_bytes => &bytes[..max_bytes],
+        _ => bytes,
+    };
+
+    let mut text = match formats.bytea {
+        ByteaEncoding::Base64 => BASE64.encode(shown),
+        ByteaEncoding::Hex => hex(shown),
+        ByteaEncoding::Escape => format!("\\x{}", hex(shown)),
+    };
+    if shown.len() < bytes.len() {
+        let _ = write!(text, "...({} bytes)", bytes.len());
+    }
+
+    Value::String(text)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(text, "{:02x}", byte);
+    }
+    text
+}
+
+/// A NUMERIC as Postgres prints it, with all of its digits and its scale.
+/// `BigDecimal` drops the scale, 10.50 would come out as 10.5000.
+struct NumericText(String);
+
+impl Type<Postgres> for NumericText {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(oid::NUMERIC))
+    }
+}
+
+impl Decode<'_, Postgres> for NumericText {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => numeric_text(value.as_bytes()?).map(Self),
+            PgValueFormat::Text => Ok(Self(value.as_str()?.to_string())),
+        }
+    }
+}
+
+/// Formats the binary NUMERIC in `bytes`: the number of base 10000 digits,
+/// the weight of the first one, the sign and the number of decimal digits
+/// after the point, followed by the digits.
+fn numeric_text(bytes: &[u8]) -> Result<String, BoxDynError> {
+    let field = |index: usize| -> Result<u16, BoxDynError> {
+        match bytes.get(index * 2..index * 2 + 2) {
+            Some(field) => Ok(u16::from_be_bytes([field[0], field[1]])),
+            None => Err("NUMERIC value is cut short".into()),
+        }
+    };
+
+    let count = field(0)? as usize;
+    let weight = field(1)? as i16 as isize;
+    let sign = field(2)?;
+    let scale = field(3)? as usize;
+    let digits = (0..count)
+        .map(|index| field(4 + index))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Digits before the first and after the last one sent are zeros
+    let digit = |index: isize| {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| digits.get(index))
+            .copied()
+            .unwrap_or(0)
+    };
+
+    let mut text = match sign {
+        0x0000 => String::new(),
+        0x4000 => String::from("-"),
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {
+            return Err(
+                format!("NUMERIC has an invalid sign {sign:#x}").into()
+            );
+        }
+    };
+
+    if weight < 0 {
+        text.push('0');
+    }
+    for index in 0..=weight {
+        match index {
+            0 => write!(text, "{}", digit(index))?,
+            _ => write!(text, "{:04}", digit(index))?,
+        }
+    }
+
+    if scale > 0 {
+        let mut fraction = String::with_capacity(scale + 4);
+        let mut index = weight + 1;
+        while fraction.len() < scale {
+            write!(fraction, "{:04}", digit(index))?;
+            index += 1;
+        }
+        fraction.truncate(scale);
+        text.push('.');
+        text.push_str(&fraction);
+    }
+
+    Ok(text)
+}
+
+/// A `money` amount as a plain decimal, without currency symbol or group
+/// separators. It is sent in cents, assuming the two fractional digits of
+/// nearly every `lc_monetary`.
+struct MoneyText(String);
+
+impl Type<Postgres> for MoneyText {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(oid::MONEY))
+    }
+}
+
+impl Decode<'_, Postgres> for MoneyText {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        if value.format() == PgValueFormat::Text {
+            return Ok(Self(value.as_str()?.to_string()));
+        }
+
+        let cents = i64::from_be_bytes(value.as_bytes()?.try_into()?);
+        let sign = if cents < 0 { "-" } else { "" };
+        let cents = cents.unsigned_abs();
+        Ok(Self(format!("{}{}.{:02}", sign, cents / 100, cents % 100)))
+    }
+}
+
+/// A `time with time zone`, written like Postgres does, e.g. `10:30:00+02`
+/// or `18:00:00.5-05:30`.
+#[derive(Serialize)]
+#[serde(transparent)]
+struct TimeTzText(String);
+
+impl Type<Postgres> for TimeTzText {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(oid::TIMETZ))
+    }
+}
+
+impl Decode<'_, Postgres> for TimeTzText {
+    /// The binary form is the time in microseconds, followed by the offset
+    /// in seconds west of UTC.
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        if value.format() == PgValueFormat::Text {
+            return Ok(Self(value.as_str()?.to_string()));
+        }
+
+        let bytes = value.as_bytes()?;
+        if bytes.len() != 12 {
+            return Err(format!(
+                "timetz has {} bytes instead of 12",
+                bytes.len()
+            )
+            .into());
+        }
+        let micros = i64::from_be_bytes(bytes[..8].try_into()?);
+        let west = i32::from_be_bytes(bytes[8..].try_into()?);
+
+        let mut text = String::new();
+        write_time(&mut text, micros.unsigned_abs());
+
+        // The offset leaves out minutes and seconds that are zero
+        let sign = if west > 0 { '-' } else { '+' };
+        let offset = west.unsigned_abs();
+        let _ = write!(text, "{}{:02}", sign, offset / 3600);
+        if offset % 3600 != 0 {
+            let _ = write!(text, ":{:02}", offset / 60 % 60);
+        }
+        if offset % 60 != 0 {
+            let _ = write!(text, ":{:02}", offset % 60);
+        }
+
+        Ok(Self(text))
+    }
+}
+
+/// An `interval` as Postgres writes it by default, e.g. `1 year 2 mons
+/// 3 days 04:05:06.789`.
+#[derive(Serialize)]
+#[serde(transparent)]
+struct IntervalText(String);
+
+impl Type<Postgres> for IntervalText {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(oid::INTERVAL))
+    }
+}
+
+impl Decode<'_, Postgres> for IntervalText {
+    /// The binary form is the time in microseconds, the days and the
+    /// months, each kept apart since none of them converts exactly.
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        if value.format() == PgValueFormat::Text {
+            return Ok(Self(value.as_str()?.to_string()));
+        }
+
+        let bytes = value.as_bytes()?;
+        if bytes.len() != 16 {
+            return Err(format!(
+                "interval has {} bytes instead of 16",
+                bytes.len()
+            )
+            .into());
+        }
+        let micros = i64::from_be_bytes(bytes[..8].try_into()?);
+        let days = i32::from_be_bytes(bytes[8..12].try_into()?);
+        let months = i32::from_be_bytes(bytes[12..].try_into()?);
+
+        Ok(Self(interval_text(micros, days, months)))
+    }
+}
+
+/// Follows `IntervalStyle` `postgres`: the non-zero parts, where a positive
+/// one after a negative one gets a plus, and the time if it isn't zero or
+/// nothing else is there.
+fn interval_text(micros: i64, days: i32, months: i32) -> String {
+    let mut text = String::new();
+    let mut after_negative = false;
+
+    let parts = [(months / 12, "year"), (months % 12, "mon"), (days, "day")];
+    for (value, unit) in parts {
+        if value == 0 {
+            continue;
+        }
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        if after_negative && value > 0 {
+            text.push('+');
+        }
+        let plural = if value == 1 { "" } else { "s" };
+        let _ = write!(text, "{} {}{}", value, unit, plural);
+        after_negative = value < 0;
+    }
+
+    if micros != 0 || text.is_empty() {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        if micros < 0 {
+            text.push('-');
+        } else if after_negative {
+            text.push('+');
+        }
+
+        write_time(&mut text, micros.unsigned_abs());
+    }
+
+    text
+}
+
+/// Writes `micros` as hours, minutes and seconds, with the fraction of a
+/// second only as far as it isn't zero.
+fn write_time(text: &mut String, micros: u64) {
+    let seconds = micros / 1_000_000;
+    let _ = write!(
+        text,
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    let fraction = micros % 1_000_000;
+    if fraction > 0 {
+        let fraction = format!("{:06}", fraction);
+        let _ = write!(text, ".{}", fraction.trim_end_matches('0'));
+    }
+}
+
+/// A UUID in its canonical form, lowercase and hyphenated.
+#[derive(Serialize)]
+#[serde(transparent)]
+struct UuidText(String);
+
+impl Type<Postgres> for UuidText {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(oid::UUID))
+    }
+}
+
+impl Decode<'_, Postgres> for UuidText {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        if value.format() == PgValueFormat::Text {
+            return Ok(Self(value.as_str()?.to_string()));
+        }
+
+        let bytes = value.as_bytes()?;
+        if bytes.len() != 16 {
+            return Err(format!(
+                "UUID has {} bytes instead of 16",
+                bytes.len()
+            )
+            .into());
+        }
+
+        let mut text = String::with_capacity(36);
+        for (index, byte) in bytes.iter().enumerate() {
+            if matches!(index, 4 | 6 | 8 | 10) {
+                text.push('-');
+            }
+            write!(text, "{:02x}", byte)?;
+        }
+        Ok(Self(text))
+    }
+}
+
+/// An `inet` or `cidr` value.
+struct Inet {
+    addr: IpAddr,
+    prefix: u8,
+    /// Networks always show their prefix, hosts only if it isn't all of
+    /// the address.
+    is_cidr: bool,
+}
+
+impl Inet {
+    fn to_json(&self, inet: InetOutput) -> Value {
+        match inet {
+            InetOutput::Text => Value::String(self.to_string()),
+            InetOutput::Object => serde_json::json!({
+                "addr": self.addr.to_string(),
+                "prefix": self.prefix,
+            }),
+        }
+    }
+}
+
+impl Display for Inet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let max_prefix = match self.addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        match self.is_cidr || self.prefix != max_prefix {
+            true => write!(f, "{}/{}", self.addr, self.prefix),
+            false => write!(f, "{}", self.addr),
+        }
+    }
+}
+
+impl Type<Postgres> for Inet {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(oid::INET))
+    }
+}
+
+impl Decode<'_, Postgres> for Inet {
+    /// The binary form is the address family, the prefix length, whether
+    /// it is a `cidr`, the length of the address and the address itself.
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        if value.format() == PgValueFormat::Text {
+            let text = value.as_str()?;
+            let (addr, prefix) = match text.split_once('/') {
+                Some((addr, prefix)) => (addr.parse()?, Some(prefix.parse()?)),
+                None => (text.parse()?, None),
+            };
+            let max_prefix =
+                if matches!(addr, IpAddr::V4(_)) { 32 } else { 128 };
+            return Ok(Self {
+                addr,
+                prefix: prefix.unwrap_or(max_prefix),
+                is_cidr: prefix.is_some(),
+            });
+        }
+
+        let bytes = value.as_bytes()?;
+        let addr = match bytes {
+            [_, _, _, 4, addr @ ..] => {
+                IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(addr)?))
+            }
+            [_, _, _, 16, addr @ ..] => {
+                IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(addr)?))
+            }
+            _ => return Err("inet value has an invalid address".into()),
+        };
+
+        Ok(Self { addr, prefix: bytes[1], is_cidr: bytes[2] == 1 })
+    }
+}
+
+/// A `macaddr` or `macaddr8`, as colon separated lowercase hex.
+#[derive(Serialize)]
+#[serde(transparent)]
+struct MacAddrText(String);
+
+impl Type<Postgres> for MacAddrText {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(oid::MACADDR))
+    }
+}
+
+impl Decode<'_, Postgres> for MacAddrText {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        if value.format() == PgValueFormat::Text {
+            return Ok(Self(value.as_str()?.to_string()));
+        }
+
+        let bytes = value.as_bytes()?;
+        if bytes.len() != 6 && bytes.len() != 8 {
+            return Err(format!(
+                "MAC address has {} bytes instead of 6 or 8",
+                bytes.len()
+            )
+            .into());
+        }
+
+        let mut text = String::with_capacity(bytes.len() * 3);
+        for (index, byte) in bytes.iter().enumerate() {
+            if index > 0 {
+                text.push(':');
+            }
+            write!(text, "{:02x}", byte)?;
+        }
+        Ok(Self(text))
+    }
+}
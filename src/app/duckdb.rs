@@ -0,0 +1,215 @@
+use std::path::Path;
+use std::process::Stdio;
+
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use futures::stream::{self, BoxStream};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+use tokio_stream::StreamExt;
+
+use crate::{
+    Backend, ColumnDescription, ConnectionString, JsonColumns, JsonRow,
+    NumericOutput, decoders::NumberKind,
+};
+
+const DUCKDB_BIN: &str = "duckdb";
+
+/// DuckDB backend, selected by `duckdb://` URIs.
+///
+/// There is no embeddable DuckDB driver in our dependency tree, so queries
+/// go through the `duckdb` CLI. Every row is rendered with DuckDB's own
+/// `to_json`, which writes HUGEINT and wide DECIMAL with all their digits,
+/// LIST as arrays, STRUCT and MAP as objects and INTERVAL as its textual
+/// form. The query is described first, for the columns whose numbers are
+/// written as `--numeric` says.
+pub struct DuckDb {
+    pub name: String,
+    path: String,
+    numeric: NumericOutput,
+}
+
+impl DuckDb {
+    pub async fn new(
+        ConnectionString { name, uri, numeric, .. }: ConnectionString,
+    ) -> Result<Self> {
+        let path = uri
+            .split_once("://")
+            .map(|(_, path)| path)
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| anyhow!("Expected duckdb://<path> for '{}'", name))?
+            .to_string();
+
+        // The CLI would quietly create a missing file, which for a
+        // mistyped path only hides the mistake
+        if !Path::new(&path).is_file() {
+            bail!("DuckDB file '{}' for '{}' does not exist", path, name);
+        }
+
+        let db = Self { name, path, numeric: numeric.unwrap_or_default() };
+        db.run::<Value>("SELECT 1".to_string())
+            .collect::<Result<Vec<_>>>()
+            .await?;
+
+        Ok(db)
+    }
+
+    /// How the numbers of each column of `query` are written.
+    async fn number_kinds(&self, query: &str) -> Result<Vec<NumberKind>> {
+        let columns = self.describe(query).await?;
+
+        Ok(columns
+            .iter()
+            .map(|column| number_kind(&column.type_name))
+            .collect())
+    }
+
+    /// Runs `statement` against the read-only database, which must return a
+    /// single JSON column, and yields one parsed value per output line.
+    fn run<T: DeserializeOwned + Send + 'static>(
+        &self,
+        statement: String,
+    ) -> BoxStream<'_, Result<T>> {
+        let child = Command::new(DUCKDB_BIN)
+            .arg("-noheader")
+            .arg("-list")
+            .arg("-readonly")
+            .arg(&self.path)
+            .arg("-c")
+            .arg(statement)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| {
+                format!("Failed to run '{}', is DuckDB installed?", DUCKDB_BIN)
+            });
+
+        let mut child = match child {
+            Ok(child) => child,
+            Err(err) => return Box::pin(stream::once(async { Err(err) })),
+        };
+
+        // Read stderr alongside stdout, or a chatty failure could fill its
+        // pipe and block the CLI before stdout ever closes
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let errors = tokio::spawn(async move {
+            let mut errors = String::new();
+            stderr.read_to_string(&mut errors).await.map(|_| errors)
+        });
+
+        let lines =
+            BufReader::new(child.stdout.take().expect("stdout is piped"))
+                .lines();
+
+        Box::pin(stream::try_unfold(
+            Some((child, lines, errors)),
+            |state| async move {
+                let Some((mut child, mut lines, errors)) = state else {
+                    return Ok(None);
+                };
+
+                if let Some(line) = lines.next_line().await? {
+                    let value =
+                        serde_json::from_str(&line).with_context(|| {
+                            format!("Unexpected DuckDB output '{}'", line)
+                        })?;
+
+                    return Ok(Some((value, Some((child, lines, errors)))));
+                }
+
+                let status = child.wait().await?;
+                let errors = errors.await??;
+                if !status.success() {
+                    bail!("{}", errors.trim());
+                }
+
+                Ok(None)
+            },
+        ))
+    }
+}
+
+/// How `to_json` writes the numbers of a column of `type_name`, or of the
+/// elements of a list of them.
+fn number_kind(type_name: &str) -> NumberKind {
+    let element = type_name.trim_end_matches("[]");
+    let scale = element
+        .strip_prefix("DECIMAL(")
+        .and_then(|args| args.strip_suffix(')'))
+        .and_then(|args| args.split_once(','))
+        .and_then(|(_, scale)| scale.trim().parse().ok());
+
+    match (element, scale) {
+        (_, Some(scale)) => NumberKind::Decimal(scale),
+        ("HUGEINT" | "UHUGEINT", _) => NumberKind::Wide,
+        _ => NumberKind::Plain,
+    }
+}
+
+/// The query is wrapped in a subquery, so it has to be a single statement
+/// without the trailing semicolon.
+fn as_subquery(query: &str) -> &str {
+    query.trim().trim_end_matches(';').trim_end()
+}
+
+#[async_trait]
+impl Backend for DuckDb {
+    async fn connect(connection_string: ConnectionString) -> Result<Self> {
+        Self::new(connection_string).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dialects(&self) -> &'static [&'static str] {
+        &["duckdb"]
+    }
+
+    fn fetch<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<JsonRow>> {
+        let statement = format!(
+            "SELECT to_json(_row) FROM ({}) AS _row",
+            as_subquery(query)
+        );
+
+        Box::pin(
+            stream::once(self.number_kinds(query))
+                .map_ok(move |kinds| {
+                    self.run(statement.clone()).map(move |row| {
+                        row.map(|row: JsonColumns| {
+                            row.with_numbers(&kinds, self.numeric)
+                                .into_row(&self.name)
+                        })
+                    })
+                })
+                .try_flatten(),
+        )
+    }
+
+    async fn describe(&self, query: &str) -> Result<Vec<ColumnDescription>> {
+        let statement = format!(
+            "SELECT to_json(_column) FROM (DESCRIBE {}) AS _column",
+            as_subquery(query)
+        );
+
+        let columns: Vec<Value> =
+            self.run(statement).collect::<Result<_>>().await?;
+
+        Ok(columns
+            .into_iter()
+            .map(|column| ColumnDescription {
+                name: column["column_name"].as_str().unwrap_or("").to_string(),
+                type_name: column["column_type"]
+                    .as_str()
+                    .unwrap_or("")
+                    .to_string(),
+            })
+            .collect())
+    }
+
+    async fn close(&self) {}
+}
@@ -0,0 +1,12 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+
+/// Reads a setting given in seconds, such as a timeout or a backoff. It has
+/// to be a positive number, `setting` names it in the error.
+pub fn seconds(value: f64, setting: &str) -> Result<Duration> {
+    Duration::try_from_secs_f64(value)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+        .ok_or_else(|| anyhow!("Invalid {} of {} seconds", setting, value))
+}
@@ -0,0 +1,148 @@
+use std::{fmt, fs::File, io::Write, path::Path, sync::Mutex, time::Duration};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::postgres::PgDatabaseError;
+
+/// A database that did not deliver its rows, or not all of their values,
+/// reported as one JSON line.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ErrorRecord {
+    pub db_name: String,
+    pub stage: ErrorStage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sqlstate: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorStage {
+    Connect,
+    Query,
+    /// The query ran out of time, on the client or on the server.
+    Timeout,
+    /// Values of a column were written as null, they could not be decoded.
+    /// The rows themselves were delivered.
+    Decode,
+}
+
+/// SQLSTATE of a statement the server cancelled, e.g. for running past
+/// `statement_timeout`.
+const QUERY_CANCELED: &str = "57014";
+
+/// A query stopped on the client for taking too long.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimedOut {
+    /// Ran past the timeout of its database.
+    Query(Duration),
+    /// Still running when the deadline of the run passed.
+    Deadline(Duration),
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Query(timeout) => {
+                write!(f, "Query timed out after {:?}", timeout)
+            }
+            Self::Deadline(deadline) => {
+                write!(f, "Deadline of {:?} for the run passed", deadline)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Which targets of a run delivered their rows and which did not, written
+/// after the error records.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct Summary {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl ErrorRecord {
+    /// Errors reported by the database itself keep their SQLSTATE, and for
+    /// Postgres also the detail and hint. Anything else only has a message.
+    /// Timeouts get the `Timeout` stage, whatever `stage` says.
+    pub fn new(db_name: &str, stage: ErrorStage, err: &anyhow::Error) -> Self {
+        let mut record = Self {
+            db_name: db_name.to_string(),
+            stage,
+            sqlstate: None,
+            message: format!("{:#}", err),
+            detail: None,
+            hint: None,
+        };
+
+        if let Some(sqlx::Error::Database(db_err)) =
+            err.downcast_ref::<sqlx::Error>()
+        {
+            record.sqlstate = db_err.code().map(|code| code.to_string());
+            record.message = db_err.message().to_string();
+            if record.sqlstate.as_deref() == Some(QUERY_CANCELED) {
+                record.stage = ErrorStage::Timeout;
+            }
+
+            if let Some(pg_err) = db_err.try_downcast_ref::<PgDatabaseError>()
+            {
+                record.detail = pg_err.detail().map(str::to_string);
+                record.hint = pg_err.hint().map(str::to_string);
+            }
+        }
+
+        if err.downcast_ref::<TimedOut>().is_some() {
+            record.stage = ErrorStage::Timeout;
+        }
+
+        record
+    }
+}
+
+/// Where error records go: stderr, or a side-channel file so they don't mix
+/// with other diagnostics.
+pub enum ErrorLog {
+    Stderr,
+    File(Mutex<File>),
+}
+
+impl ErrorLog {
+    pub fn open(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::Stderr);
+        };
+
+        let file = File::create(path).with_context(|| {
+            format!("Failed to create error file '{}'", path.display())
+        })?;
+
+        Ok(Self::File(Mutex::new(file)))
+    }
+
+    pub fn write(&self, record: &ErrorRecord) -> Result<()> {
+        self.write_line(serde_json::to_string(record)?)
+    }
+
+    pub fn write_summary(&self, summary: &Summary) -> Result<()> {
+        self.write_line(serde_json::json!({ "summary": summary }).to_string())
+    }
+
+    fn write_line(&self, line: String) -> Result<()> {
+        match self {
+            Self::Stderr => eprintln!("{}", line),
+            Self::File(file) => {
+                let mut file = file.lock().expect("error file lock poisoned");
+                writeln!(file, "{}", line)?;
+                file.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use serde_json::{Map, Value};
+use tokio::fs;
+
+use crate::{
+    Backend, ColumnDescription, ConnectionString, JsonRow, SqliteDb, decoders,
+};
+
+/// CSV and JSON-lines files, selected by `file://` URIs.
+///
+/// Every matching file is loaded into its own table of a private in-memory
+/// SQLite database, named after the file stem, and the query runs there.
+pub struct FileDb {
+    db: SqliteDb,
+}
+
+impl FileDb {
+    pub async fn new(
+        ConnectionString { name, uri, .. }: ConnectionString,
+    ) -> Result<Self> {
+        let pattern = uri
+            .split_once("://")
+            .map(|(_, path)| path)
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| anyhow!("Expected file://<path> for '{}'", name))?;
+
+        let files = glob::glob(pattern)
+            .with_context(|| format!("Invalid file pattern '{}'", pattern))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if files.is_empty() {
+            bail!("No files match '{}' for '{}'", pattern, name);
+        }
+
+        let mut tables = Vec::with_capacity(files.len());
+        for file in files {
+            tables.push(read_table(&file).await?);
+        }
+
+        let db = load_tables(name, tables).await?;
+
+        Ok(Self { db })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Boolean,
+    Text,
+    /// JSON text, such as nested values or exact decimals, written back as
+    /// JSON.
+    Json,
+}
+
+impl ColumnType {
+    fn declared(self) -> &'static str {
+        match self {
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Real => "REAL",
+            ColumnType::Boolean => "BOOLEAN",
+            ColumnType::Text => "TEXT",
+            // BLOB affinity leaves the text alone, and unlike TEXT it is
+            // reported for the column under any alias
+            ColumnType::Json => "JSON BLOB",
+        }
+    }
+
+    /// The narrowest type that holds values of both, integers widen to
+    /// reals and anything else mixed falls back to text.
+    fn widen(self, other: Self) -> Self {
+        match (self, other) {
+            _ if self == other => self,
+            (ColumnType::Integer, ColumnType::Real)
+            | (ColumnType::Real, ColumnType::Integer) => ColumnType::Real,
+            _ => ColumnType::Text,
+        }
+    }
+
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(ColumnType::Boolean),
+            Value::Number(n) if n.is_i64() => Some(ColumnType::Integer),
+            Value::Number(_) => Some(ColumnType::Real),
+            Value::String(_) => Some(ColumnType::Text),
+            Value::Array(_) | Value::Object(_) => Some(ColumnType::Json),
+        }
+    }
+}
+
+/// Rows read from a file, before they are loaded into SQLite.
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<(String, ColumnType)>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    /// Builds a table from JSON objects. Columns are the union of all keys
+    /// in the order they are first seen, typed to hold all their non-null
+    /// values. Values of text columns that aren't strings are kept as their
+    /// JSON text.
+    pub fn from_objects(
+        name: String,
+        objects: Vec<Map<String, Value>>,
+    ) -> Self {
+        let mut columns: Vec<(String, Option<ColumnType>)> = Vec::new();
+
+        for object in &objects {
+            for (key, value) in object {
+                let value_type = ColumnType::of(value);
+                match columns.iter_mut().find(|(name, _)| name == key) {
+                    Some((_, ty)) => {
+                        *ty = match (*ty, value_type) {
+                            (Some(ty), Some(other)) => Some(ty.widen(other)),
+                            (ty, other) => ty.or(other),
+                        }
+                    }
+                    None => columns.push((key.clone(), value_type)),
+                }
+            }
+        }
+
+        let columns: Vec<_> = columns
+            .into_iter()
+            .map(|(name, ty)| (name, ty.unwrap_or(ColumnType::Text)))
+            .collect();
+
+        let rows = objects
+            .into_iter()
+            .map(|mut object| {
+                columns
+                    .iter()
+                    .map(|(name, ty)| match object.remove(name) {
+                        None => Value::Null,
+                        Some(value @ (Value::Null | Value::String(_))) => {
+                            value
+                        }
+                        Some(value) if *ty == ColumnType::Text => {
+                            Value::String(value.to_string())
+                        }
+                        Some(value) => value,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self { name, columns, rows }
+    }
+}
+
+async fn read_table(path: &Path) -> Result<Table> {
+    let name = table_name(path)?;
+    let content = fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read '{}'", path.display()))?;
+
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("csv") => read_delimited(name, &content, b','),
+        Some("tsv") => read_delimited(name, &content, b'\t'),
+        Some("jsonl" | "ndjson") => read_json_lines(name, &content),
+        _ => bail!(
+            "Unsupported file '{}', expected .csv, .tsv, .jsonl or .ndjson",
+            path.display()
+        ),
+    }
+    .with_context(|| format!("Failed to load '{}'", path.display()))
+}
+
+pub fn table_name(path: &Path) -> Result<String> {
+    let stem = path
+        .file_stem()
+        .ok_or_else(|| anyhow!("'{}' has no file name", path.display()))?;
+
+    Ok(stem
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect())
+}
+
+/// CSV cells are all text, so each column gets the narrowest type every
+/// non-empty cell parses as. Empty cells become NULL.
+fn read_delimited(
+    name: String,
+    content: &[u8],
+    delimiter: u8,
+) -> Result<Table> {
+    let mut reader =
+        csv::ReaderBuilder::new().delimiter(delimiter).from_reader(content);
+
+    let headers: Vec<String> =
+        reader.headers()?.iter().map(str::to_string).collect();
+    let records = reader.records().collect::<Result<Vec<_>, _>>()?;
+
+    let columns: Vec<(String, ColumnType)> = headers
+        .into_iter()
+        .enumerate()
+        .map(|(index, header)| {
+            let cells = records
+                .iter()
+                .filter_map(|record| record.get(index))
+                .filter(|cell| !cell.is_empty());
+
+            (header, infer_type(cells))
+        })
+        .collect();
+
+    let rows = records
+        .iter()
+        .map(|record| {
+            columns
+                .iter()
+                .enumerate()
+                .map(|(index, (_, ty))| match record.get(index) {
+                    None | Some("") => Value::Null,
+                    Some(cell) => parse_cell(cell, *ty),
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(Table { name, columns, rows })
+}
+
+fn infer_type<'a>(cells: impl Iterator<Item = &'a str> + Clone) -> ColumnType {
+    let numeric = !cells.clone().any(is_padded);
+
+    if numeric && cells.clone().all(|cell| cell.parse::<i64>().is_ok()) {
+        ColumnType::Integer
+    } else if numeric && cells.clone().all(|cell| cell.parse::<f64>().is_ok())
+    {
+        ColumnType::Real
+    } else if cells.clone().all(|cell| cell.parse::<bool>().is_ok()) {
+        ColumnType::Boolean
+    } else {
+        ColumnType::Text
+    }
+}
+
+/// Zip codes and IDs such as `007` or `+49` would lose their leading zeros
+/// or sign as numbers, so columns holding them stay text.
+fn is_padded(cell: &str) -> bool {
+    let unsigned = cell.strip_prefix('-').unwrap_or(cell);
+
+    cell.starts_with('+')
+        || (unsigned.len() > 1
+            && unsigned.starts_with('0')
+            && !unsigned.starts_with("0."))
+}
+
+fn parse_cell(cell: &str, ty: ColumnType) -> Value {
+    match ty {
+        ColumnType::Integer => cell.parse::<i64>().map(Value::from).ok(),
+        ColumnType::Real => cell.parse::<f64>().map(Value::from).ok(),
+        ColumnType::Boolean => cell.parse::<bool>().map(Value::from).ok(),
+        ColumnType::Text | ColumnType::Json => None,
+    }
+    .unwrap_or_else(|| Value::String(cell.to_string()))
+}
+
+fn read_json_lines(name: String, content: &[u8]) -> Result<Table> {
+    let content = std::str::from_utf8(content)?;
+    let mut objects = Vec::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let value = serde_json::from_str(line).with_context(|| {
+            format!("Invalid JSON on line {}", line_number + 1)
+        })?;
+        match decoders::round_numbers(value) {
+            Value::Object(object) => objects.push(object),
+            _ => bail!("Line {} is not a JSON object", line_number + 1),
+        }
+    }
+
+    Ok(Table::from_objects(name, objects))
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Creates and fills one SQLite table per `Table` in a fresh in-memory
+/// database.
+pub async fn load_tables(
+    name: String,
+    tables: Vec<Table>,
+) -> Result<SqliteDb> {
+    let db = SqliteDb::in_memory(name).await?;
+    let mut table_names = HashSet::new();
+
+    for table in tables {
+        if !table_names.insert(table.name.clone()) {
+            bail!(
+                "More than one file would be loaded as table '{}'",
+                table.name
+            );
+        }
+
+        let columns = table
+            .columns
+            .iter()
+            .map(|(column, ty)| {
+                format!("{} {}", quote_identifier(column), ty.declared())
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        sqlx::query(&format!(
+            "CREATE TABLE {} ({})",
+            quote_identifier(&table.name),
+            columns
+        ))
+        .execute(db.pool())
+        .await?;
+
+        let insert = format!(
+            "INSERT INTO {} VALUES ({})",
+            quote_identifier(&table.name),
+            vec!["?"; table.columns.len()].join(", ")
+        );
+
+        let mut tx = db.pool().begin().await?;
+        for row in table.rows {
+            let mut query = sqlx::query(&insert);
+            for value in row {
+                query = match value {
+                    Value::Null => query.bind(None::<String>),
+                    Value::Bool(v) => query.bind(v),
+                    Value::Number(n) => match n.as_i64() {
+                        Some(v) => query.bind(v),
+                        None => query.bind(n.as_f64()),
+                    },
+                    Value::String(v) => query.bind(v),
+                    nested => query.bind(nested.to_string()),
+                };
+            }
+            query.execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+    }
+
+    Ok(db)
+}
+
+#[async_trait]
+impl Backend for FileDb {
+    async fn connect(connection_string: ConnectionString) -> Result<Self> {
+        Self::new(connection_string).await
+    }
+
+    fn name(&self) -> &str {
+        self.db.name()
+    }
+
+    fn dialects(&self) -> &'static [&'static str] {
+        &["file", "sqlite"]
+    }
+
+    fn fetch<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<JsonRow>> {
+        self.db.fetch(query)
+    }
+
+    async fn describe(&self, query: &str) -> Result<Vec<ColumnDescription>> {
+        self.db.describe(query).await
+    }
+
+    async fn close(&self) {
+        self.db.close().await
+    }
+}
@@ -0,0 +1,311 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::{Context, Result, bail};
+use percent_encoding::{
+    NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode,
+};
+use url::Url;
+
+use crate::{BackendKind, ConnectionString};
+
+/// Settings that go into the authority and path of a Postgres URI. Every
+/// other keyword is passed on as a URI parameter.
+const URI_KEYWORDS: [&str; 5] = ["user", "password", "host", "port", "dbname"];
+
+/// Keyword/value pairs of a libpq connection string, in the order given.
+/// Repeated keys are kept, our own parameters such as `schemas` may repeat.
+type Params = Vec<(String, String)>;
+
+/// Applies libpq conventions to Postgres entries: keyword/value connection
+/// strings like `host=db1 dbname=app` and `service=name` lookups in
+/// `pg_service.conf`, also through `PGSERVICE`. Values given in the entry
+/// win over the service file. Whatever is still missing afterwards, the
+/// password included, is filled in when connecting from the PG* variables
+/// and `.pgpass`.
+pub fn resolve(
+    connection_string: ConnectionString,
+) -> Result<ConnectionString> {
+    let ConnectionString { name, uri, .. } = &connection_string;
+
+    let (mut params, is_uri) = if uri.contains("://") {
+        if !matches!(BackendKind::from_uri(uri), Ok(BackendKind::Postgres)) {
+            return Ok(connection_string);
+        }
+
+        let params = uri_params(uri)
+            .with_context(|| format!("Invalid Postgres URI for '{}'", name))?;
+        (params, true)
+    } else {
+        match parse_conninfo(uri).with_context(|| {
+            format!("Invalid connection string for '{}'", name)
+        })? {
+            Some(params) => (params, false),
+            None => return Ok(connection_string),
+        }
+    };
+
+    let service = take(&mut params, "service")
+        .or_else(|| env::var("PGSERVICE").ok())
+        .filter(|service| !service.is_empty());
+
+    match service {
+        Some(service) => {
+            let defaults = load_service(&service)
+                .with_context(|| format!("Failed to resolve '{}'", name))?;
+            for (key, value) in defaults {
+                if !params.iter().any(|(known, _)| *known == key) {
+                    params.push((key, value));
+                }
+            }
+        }
+        None if is_uri => return Ok(connection_string),
+        None => {}
+    }
+
+    Ok(ConnectionString { uri: to_uri(params)?, ..connection_string })
+}
+
+/// Resolves every entry, and every one of their `endpoints`.
+pub fn resolve_all(
+    connection_strings: Vec<ConnectionString>,
+) -> Result<Vec<ConnectionString>> {
+    connection_strings
+        .into_iter()
+        .map(|connection_string| {
+            let endpoints = connection_string
+                .endpoints
+                .iter()
+                .map(|endpoint| {
+                    Ok(resolve(ConnectionString {
+                        name: connection_string.name.clone(),
+                        uri: endpoint.clone(),
+                        ..Default::default()
+                    })?
+                    .uri)
+                })
+                .collect::<Result<_>>()?;
+
+            Ok(ConnectionString { endpoints, ..resolve(connection_string)? })
+        })
+        .collect()
+}
+
+fn take(params: &mut Params, key: &str) -> Option<String> {
+    let index = params.iter().position(|(known, _)| known == key)?;
+    Some(params.remove(index).1)
+}
+
+fn uri_params(uri: &str) -> Result<Params> {
+    let url = Url::parse(uri)?;
+    let decode = |value: &str| {
+        percent_decode_str(value).decode_utf8_lossy().to_string()
+    };
+
+    let mut params = Params::new();
+    if !url.username().is_empty() {
+        params.push(("user".to_string(), decode(url.username())));
+    }
+    if let Some(password) = url.password() {
+        params.push(("password".to_string(), decode(password)));
+    }
+    if let Some(host) = url.host_str() {
+        params.push(("host".to_string(), decode(host)));
+    }
+    if let Some(port) = url.port() {
+        params.push(("port".to_string(), port.to_string()));
+    }
+    let dbname = url.path().trim_start_matches('/');
+    if !dbname.is_empty() {
+        params.push(("dbname".to_string(), decode(dbname)));
+    }
+
+    params.extend(
+        url.query_pairs()
+            .map(|(key, value)| (key.to_string(), value.to_string())),
+    );
+
+    Ok(params)
+}
+
+/// Parses `host=db1 port=5432 dbname='my app'`. Returns `None` for strings
+/// that are not in keyword/value form, so they fail later as usual.
+fn parse_conninfo(conninfo: &str) -> Result<Option<Params>> {
+    if !conninfo.contains('=') {
+        return Ok(None);
+    }
+
+    let mut params = Params::new();
+    let mut chars = conninfo.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let key: String = std::iter::from_fn(|| {
+            chars.next_if(|c| *c != '=' && !c.is_whitespace())
+        })
+        .collect();
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.next() != Some('=') {
+            bail!("Missing '=' after '{}'", key);
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'\'').is_some() {
+            loop {
+                match chars.next() {
+                    Some('\\') => value.extend(chars.next()),
+                    Some('\'') => break,
+                    Some(c) => value.push(c),
+                    None => bail!("Unterminated quoted value for '{}'", key),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    c => value.push(c),
+                }
+            }
+        }
+
+        params.push((key, value));
+    }
+
+    Ok(Some(params))
+}
+
+/// `~/.pg_service.conf` (or `PGSERVICEFILE`) is searched first, then
+/// `pg_service.conf` in `PGSYSCONFDIR`, like libpq does.
+fn service_files() -> Vec<PathBuf> {
+    let user_file = match env::var_os("PGSERVICEFILE") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => dirs::home_dir().map(|home| home.join(".pg_service.conf")),
+    };
+    let system_file = env::var_os("PGSYSCONFDIR")
+        .map(|dir| PathBuf::from(dir).join("pg_service.conf"));
+
+    user_file.into_iter().chain(system_file).collect()
+}
+
+fn load_service(service: &str) -> Result<Params> {
+    for path in service_files() {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Failed to read '{}'", path.display())
+                });
+            }
+        };
+
+        if let Some(params) = find_service(&content, service) {
+            return Ok(params);
+        }
+    }
+
+    bail!("Definition of service '{}' not found", service)
+}
+
+fn find_service(content: &str, service: &str) -> Option<Params> {
+    let mut params = None;
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(section) =
+            line.strip_prefix('[').and_then(|line| line.strip_suffix(']'))
+        {
+            if params.is_some() {
+                break;
+            }
+            if section.trim() == service {
+                params = Some(Params::new());
+            }
+            continue;
+        }
+
+        if let Some(params) = params.as_mut()
+            && let Some((key, value)) = line.split_once('=')
+        {
+            params.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    params
+}
+
+fn to_uri(mut params: Params) -> Result<String> {
+    let encode =
+        |value: &str| utf8_percent_encode(value, NON_ALPHANUMERIC).to_string();
+
+    let user = take(&mut params, "user");
+    let password = take(&mut params, "password");
+    let host = take(&mut params, "host");
+    let port = take(&mut params, "port");
+    let dbname = take(&mut params, "dbname").unwrap_or_default();
+
+    let mut uri = String::from("postgresql://");
+    let mut query = Params::new();
+
+    // Without a TCP host there is no authority to put the rest in, and
+    // socket directories are only understood as a parameter
+    match host.filter(|host| !host.starts_with('/')) {
+        Some(host) => {
+            if let Some(user) = &user {
+                uri.push_str(&encode(user));
+                if let Some(password) = &password {
+                    uri.push(':');
+                    uri.push_str(&encode(password));
+                }
+                uri.push('@');
+            }
+
+            if host.contains(':') {
+                uri.push_str(&format!("[{}]", host));
+            } else {
+                uri.push_str(&host);
+            }
+
+            if let Some(port) = &port {
+                uri.push(':');
+                uri.push_str(port);
+            }
+        }
+        socket => {
+            let settings = [
+                ("host", socket),
+                ("port", port),
+                ("user", user),
+                ("password", password),
+            ];
+            for (key, value) in settings {
+                if let Some(value) = value {
+                    query.push((key.to_string(), value));
+                }
+            }
+        }
+    }
+
+    uri.push('/');
+    uri.push_str(&encode(&dbname));
+
+    query.extend(
+        params
+            .into_iter()
+            .filter(|(key, _)| !URI_KEYWORDS.contains(&key.as_str())),
+    );
+
+    let mut url = Url::parse(&uri)?;
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+
+    Ok(url.to_string())
+}
@@ -1,8 +1,50 @@
 pub mod types;
 pub use types::*;
 
+pub mod backend;
+pub use backend::*;
+
 pub mod db;
 pub use db::*;
 
+pub mod decoders;
+
+pub mod duration;
+
+pub mod pool;
+
+pub mod retry;
+
+pub mod tls;
+
+pub mod ssh;
+
+pub mod relay;
+
+pub mod mysql;
+pub use mysql::*;
+
+pub mod sqlite;
+pub use sqlite::*;
+
+pub mod duckdb;
+pub use duckdb::*;
+
+pub mod clickhouse;
+pub use clickhouse::*;
+
+pub mod file;
+pub use file::*;
+
+pub mod parquet;
+pub use self::parquet::*;
+
+pub mod libpq;
+
+pub mod targets;
+
+pub mod errors;
+pub use errors::*;
+
 pub mod core;
 pub use core::*;
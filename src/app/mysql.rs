@@ -0,0 +1,190 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use futures::stream::BoxStream;
+use serde_json::Value;
+use serde_json::json;
+use sqlx::MySql;
+use sqlx::types::JsonValue;
+use tokio_stream::StreamExt;
+
+use sqlx::mysql::{
+    MySqlConnectOptions, MySqlPool, MySqlRow, types::MySqlTime,
+};
+use sqlx::{Column, Decode, Executor, Row, Type, TypeInfo, ValueRef};
+
+use crate::{
+    Backend, ColumnDescription, ColumnKeys, ConnectionString, JsonRow,
+    NumericOutput, decoders, pool,
+};
+
+/// MySQL and MariaDB backend, selected by `mysql://` and `mariadb://` URIs.
+pub struct MySqlDb {
+    pub name: String,
+    db: MySqlPool,
+    dialects: &'static [&'static str],
+    numeric: NumericOutput,
+}
+
+impl MySqlDb {
+    pub async fn new(
+        ConnectionString { name, uri, pool: settings, numeric, .. }: ConnectionString,
+    ) -> Result<Self> {
+        // MariaDB speaks the MySQL protocol, sqlx only knows the mysql scheme
+        let (uri, dialects): (_, &'static [&'static str]) = match uri
+            .split_once("://")
+        {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case("mariadb") => {
+                (format!("mysql://{}", rest), &["mariadb", "mysql"])
+            }
+            _ => (uri, &["mysql"]),
+        };
+
+        let db = pool::connect(
+            settings.as_ref(),
+            pool::DEFAULT_MAX_CONNECTIONS,
+            MySqlConnectOptions::from_str(&uri)?,
+        )
+        .await?;
+
+        Ok(Self { db, name, dialects, numeric: numeric.unwrap_or_default() })
+    }
+
+    fn row_to_json(&self, row: &MySqlRow) -> JsonRow {
+        let mut json_obj = serde_json::Map::new();
+        let mut keys = ColumnKeys::default();
+
+        json_obj
+            .insert("db_name".to_string(), Value::String(self.name.clone()));
+
+        for col in row.columns() {
+            let key = keys.next(col.name());
+            let index = col.ordinal();
+
+            let json_value = match col.type_info().name() {
+                "BOOLEAN" => decode::<bool>(row, index, |v| json!(v)),
+                "TINYINT" => decode::<i8>(row, index, |v| json!(v)),
+                "SMALLINT" => decode::<i16>(row, index, |v| json!(v)),
+                "INT" | "MEDIUMINT" => decode::<i32>(row, index, |v| json!(v)),
+                "BIGINT" => decode::<i64>(row, index, |v| json!(v)),
+                "TINYINT UNSIGNED" => decode::<u8>(row, index, |v| json!(v)),
+                "SMALLINT UNSIGNED" | "YEAR" => {
+                    decode::<u16>(row, index, |v| json!(v))
+                }
+                "INT UNSIGNED" | "MEDIUMINT UNSIGNED" => {
+                    decode::<u32>(row, index, |v| json!(v))
+                }
+                "BIGINT UNSIGNED" | "BIT" => {
+                    decode::<u64>(row, index, |v| json!(v))
+                }
+                // Going through the shortest string representation keeps
+                // 3.14 from turning into 3.140000104904175
+                "FLOAT" => decode::<f32>(row, index, |v| {
+                    match v.to_string().parse::<f64>() {
+                        Ok(num) => json!(num),
+                        Err(_) => Value::Null,
+                    }
+                }),
+                "DOUBLE" => decode::<f64>(row, index, |v| json!(v)),
+                // Sent as text, with the scale of the column
+                "DECIMAL" => match decode_as_string(row, index) {
+                    Value::String(v) => {
+                        decoders::numeric_to_json(v, self.numeric)
+                    }
+                    v => v,
+                },
+                "DATETIME" => {
+                    decode::<NaiveDateTime>(row, index, |v| json!(v))
+                }
+                "TIMESTAMP" => {
+                    decode::<DateTime<Utc>>(row, index, |v| json!(v))
+                }
+                "DATE" => decode::<NaiveDate>(row, index, |v| json!(v)),
+                // TIME is an interval and may be negative or exceed 24 hours
+                "TIME" => match row.try_get::<Option<NaiveTime>, _>(index) {
+                    Ok(Some(v)) => json!(v),
+                    Ok(None) => Value::Null,
+                    Err(_) => decode::<MySqlTime>(row, index, |v| {
+                        Value::String(v.to_string())
+                    }),
+                },
+                "JSON" => {
+                    decode::<JsonValue>(row, index, decoders::round_numbers)
+                }
+                _ => decode_as_string(row, index),
+            };
+
+            json_obj.insert(key, json_value);
+        }
+
+        json_obj
+    }
+}
+
+fn decode<'r, T>(
+    row: &'r MySqlRow,
+    index: usize,
+    to_json: impl FnOnce(T) -> Value,
+) -> Value
+where
+    T: Decode<'r, MySql> + Type<MySql>,
+{
+    match row.try_get::<Option<T>, _>(index) {
+        Ok(Some(v)) => to_json(v),
+        _ => Value::Null,
+    }
+}
+
+/// Text, enum and set columns, plus anything else we do not map explicitly.
+fn decode_as_string(row: &MySqlRow, index: usize) -> Value {
+    match row.try_get_raw(index) {
+        Ok(raw) if !raw.is_null() => {
+            match <String as Decode<MySql>>::decode(raw) {
+                Ok(s) => Value::String(s),
+                Err(_) => Value::Null,
+            }
+        }
+        _ => Value::Null,
+    }
+}
+
+#[async_trait]
+impl Backend for MySqlDb {
+    async fn connect(connection_string: ConnectionString) -> Result<Self> {
+        Self::new(connection_string).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dialects(&self) -> &'static [&'static str] {
+        self.dialects
+    }
+
+    fn fetch<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<JsonRow>> {
+        Box::pin(sqlx::query(query).fetch(&self.db).map(|row| {
+            let row = row?;
+            Ok(self.row_to_json(&row))
+        }))
+    }
+
+    async fn describe(&self, query: &str) -> Result<Vec<ColumnDescription>> {
+        let description = self.db.describe(query).await?;
+
+        Ok(description
+            .columns()
+            .iter()
+            .map(|column| ColumnDescription {
+                name: column.name().to_string(),
+                type_name: column.type_info().name().to_string(),
+            })
+            .collect())
+    }
+
+    async fn close(&self) {
+        self.db.close().await
+    }
+}
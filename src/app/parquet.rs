@@ -0,0 +1,281 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate};
+use futures::stream::BoxStream;
+use parquet::file::reader::SerializedFileReader;
+use parquet::record::{Field, Row};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    Backend, ColumnDescription, ColumnType, ConnectionString, JsonRow,
+    NumericOutput, SqliteDb, Table,
+    decoders::{self, Formats},
+    load_tables, table_name,
+};
+
+/// Parquet files, selected by `parquet://` URIs.
+///
+/// The URI points at a single file or at a dataset directory. All `.parquet`
+/// files below a directory are loaded into one table named after it, with
+/// Hive style `key=value` directories added as partition columns. Decimals
+/// are written as `--numeric` says, and binary values that aren't text as
+/// `--bytea` does.
+pub struct ParquetDb {
+    db: SqliteDb,
+}
+
+impl ParquetDb {
+    pub async fn new(connection_string: ConnectionString) -> Result<Self> {
+        let formats = Formats::from(&connection_string);
+        let ConnectionString { name, uri, .. } = connection_string;
+        let path = uri
+            .split_once("://")
+            .map(|(_, path)| PathBuf::from(path))
+            .filter(|path| !path.as_os_str().is_empty())
+            .ok_or_else(|| {
+                anyhow!("Expected parquet://<path> for '{}'", name)
+            })?;
+
+        let table =
+            tokio::task::spawn_blocking(move || read_dataset(&path, &formats))
+                .await??;
+
+        let db = load_tables(name, vec![table]).await?;
+
+        Ok(Self { db })
+    }
+}
+
+fn read_dataset(path: &Path, formats: &Formats) -> Result<Table> {
+    let files = if path.is_dir() {
+        let pattern = path.join("**").join("*.parquet");
+        glob::glob(&pattern.to_string_lossy())?
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    if files.is_empty() {
+        bail!("No Parquet files found in '{}'", path.display());
+    }
+
+    let mut objects = Vec::new();
+    let mut decimals = HashSet::new();
+    for file in files {
+        let partitions = partition_values(path, &file);
+
+        let reader = File::open(&file)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(SerializedFileReader::new(file)?))
+            .with_context(|| format!("Failed to open '{}'", file.display()))?;
+
+        for row in reader {
+            let row = row.with_context(|| {
+                format!("Failed to read '{}'", file.display())
+            })?;
+
+            let mut object = Map::new();
+            for (name, field) in row.get_column_iter() {
+                let value = match field {
+                    // SQLite would round exact numbers to doubles, they are
+                    // loaded as JSON text and read back as numbers
+                    Field::Decimal(v)
+                        if formats.numeric == NumericOutput::Exact =>
+                    {
+                        decimals.insert(name.clone());
+                        Value::String(decimal_to_string(v.data(), v.scale()))
+                    }
+                    field => field_to_json(field, formats),
+                };
+                object.insert(name.clone(), value);
+            }
+            for (key, value) in &partitions {
+                object.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            objects.push(object);
+        }
+    }
+
+    let mut table = Table::from_objects(table_name(path)?, objects);
+    for (column, ty) in &mut table.columns {
+        if decimals.contains(column) && *ty == ColumnType::Text {
+            *ty = ColumnType::Json;
+        }
+    }
+
+    Ok(table)
+}
+
+/// `dataset/year=2024/region=eu/part-0.parquet` yields `year` and `region`.
+fn partition_values(root: &Path, file: &Path) -> Vec<(String, Value)> {
+    let Some(directories) =
+        file.parent().and_then(|parent| parent.strip_prefix(root).ok())
+    else {
+        return Vec::new();
+    };
+
+    directories
+        .components()
+        .filter_map(|component| {
+            let component = component.as_os_str().to_string_lossy();
+            let (key, value) = component.split_once('=')?;
+            let value = match value.parse::<i64>() {
+                Ok(number) => json!(number),
+                Err(_) => Value::String(value.to_string()),
+            };
+
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+fn row_to_object(row: &Row, formats: &Formats) -> Map<String, Value> {
+    row.get_column_iter()
+        .map(|(name, field)| (name.clone(), field_to_json(field, formats)))
+        .collect()
+}
+
+/// Converts values into the shapes `Db::row_to_json` produces for the
+/// equivalent Postgres types.
+fn field_to_json(field: &Field, formats: &Formats) -> Value {
+    match field {
+        Field::Null => Value::Null,
+        Field::Bool(v) => json!(v),
+        Field::Byte(v) => json!(v),
+        Field::Short(v) => json!(v),
+        Field::Int(v) => json!(v),
+        Field::Long(v) => json!(v),
+        Field::UByte(v) => json!(v),
+        Field::UShort(v) => json!(v),
+        Field::UInt(v) => json!(v),
+        Field::ULong(v) => json!(v),
+        Field::Float16(v) => float_to_json(f32::from(*v)),
+        Field::Float(v) => float_to_json(*v),
+        Field::Double(v) => json!(v),
+        Field::Decimal(v) => decoders::numeric_to_json(
+            decimal_to_string(v.data(), v.scale()),
+            formats.numeric,
+        ),
+        Field::Str(v) => Value::String(v.clone()),
+        Field::Bytes(v) => match v.as_utf8() {
+            Ok(s) => Value::String(s.to_string()),
+            Err(_) => decoders::bytes_to_json(v.data(), formats),
+        },
+        Field::Date(days) => NaiveDate::from_num_days_from_ce_opt(
+            days + UNIX_EPOCH_DAYS_FROM_CE,
+        )
+        .map_or(Value::Null, |date| json!(date)),
+        Field::TimestampMillis(millis) => {
+            DateTime::from_timestamp_millis(*millis)
+                .map_or(Value::Null, |timestamp| json!(timestamp))
+        }
+        Field::TimestampMicros(micros) => {
+            DateTime::from_timestamp_micros(*micros)
+                .map_or(Value::Null, |timestamp| json!(timestamp))
+        }
+        Field::Group(row) => Value::Object(row_to_object(row, formats)),
+        Field::ListInternal(list) => Value::Array(
+            list.elements()
+                .iter()
+                .map(|field| field_to_json(field, formats))
+                .collect(),
+        ),
+        Field::MapInternal(map) => Value::Object(
+            map.entries()
+                .iter()
+                .map(|(key, value)| {
+                    let key = match field_to_json(key, formats) {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+
+                    (key, field_to_json(value, formats))
+                })
+                .collect(),
+        ),
+    }
+}
+
+const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+fn float_to_json(v: f32) -> Value {
+    match v.to_string().parse::<f64>() {
+        Ok(num) => json!(num),
+        Err(_) => Value::Null,
+    }
+}
+
+/// Parquet decimals are big-endian two's complement unscaled integers, of
+/// as many bytes as their precision needs.
+fn decimal_to_string(bytes: &[u8], scale: i32) -> String {
+    let negative = bytes.first().is_some_and(|b| b & 0x80 != 0);
+    let mut magnitude = bytes.to_vec();
+    if negative {
+        magnitude.iter_mut().for_each(|b| *b = !*b);
+        for b in magnitude.iter_mut().rev() {
+            *b = b.wrapping_add(1);
+            if *b != 0 {
+                break;
+            }
+        }
+    }
+
+    // Divided down by 10^9 at a time, the lowest nine digits first
+    let mut chunks = Vec::new();
+    while magnitude.iter().any(|b| *b != 0) {
+        let mut remainder = 0u64;
+        for b in &mut magnitude {
+            let value = remainder << 8 | u64::from(*b);
+            *b = (value / 1_000_000_000) as u8;
+            remainder = value % 1_000_000_000;
+        }
+        chunks.push(remainder);
+    }
+    let mut digits = chunks.pop().unwrap_or(0).to_string();
+    for chunk in chunks.iter().rev() {
+        digits.push_str(&format!("{:09}", chunk));
+    }
+
+    let sign = if negative { "-" } else { "" };
+    let scale = scale.max(0) as usize;
+
+    if scale == 0 {
+        return format!("{}{}", sign, digits);
+    }
+
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+
+    format!("{}{}.{}", sign, integer, fraction)
+}
+
+#[async_trait]
+impl Backend for ParquetDb {
+    async fn connect(connection_string: ConnectionString) -> Result<Self> {
+        Self::new(connection_string).await
+    }
+
+    fn name(&self) -> &str {
+        self.db.name()
+    }
+
+    fn dialects(&self) -> &'static [&'static str] {
+        &["parquet", "sqlite"]
+    }
+
+    fn fetch<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<JsonRow>> {
+        self.db.fetch(query)
+    }
+
+    async fn describe(&self, query: &str) -> Result<Vec<ColumnDescription>> {
+        self.db.describe(query).await
+    }
+
+    async fn close(&self) {
+        self.db.close().await
+    }
+}
@@ -0,0 +1,48 @@
+use anyhow::{Result, bail};
+use sqlx::{Connection, Database, Pool, pool::PoolOptions};
+
+use crate::{PoolSettings, duration::seconds};
+
+/// A one-shot run needs no more than one connection per target.
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 1;
+
+/// Opens a pool as `settings` say, connecting right away unless it is
+/// `lazy`. `default_max_connections` applies when no maximum is set.
+pub async fn connect<DB: Database>(
+    settings: Option<&PoolSettings>,
+    default_max_connections: u32,
+    connect_options: <DB::Connection as Connection>::Options,
+) -> Result<Pool<DB>> {
+    let settings = settings.cloned().unwrap_or_default();
+
+    let max_connections =
+        settings.max_connections.unwrap_or(default_max_connections);
+    if max_connections == 0 {
+        bail!("max_connections has to be at least 1");
+    }
+    let min_connections = settings.min_connections.unwrap_or(0);
+    if min_connections > max_connections {
+        bail!(
+            "min_connections ({}) is larger than max_connections ({})",
+            min_connections,
+            max_connections
+        );
+    }
+
+    let mut options = PoolOptions::<DB>::new()
+        .max_connections(max_connections)
+        .min_connections(min_connections);
+    if let Some(timeout) = settings.acquire_timeout {
+        options =
+            options.acquire_timeout(seconds(timeout, "acquire_timeout")?);
+    }
+    if let Some(timeout) = settings.idle_timeout {
+        options = options.idle_timeout(seconds(timeout, "idle_timeout")?);
+    }
+
+    if settings.lazy.unwrap_or(false) {
+        return Ok(options.connect_lazy_with(connect_options));
+    }
+
+    Ok(options.connect_with(connect_options).await?)
+}
@@ -0,0 +1,105 @@
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use anyhow::{Context, Result};
+use tokio::{
+    io::copy_bidirectional,
+    net::{TcpStream, UnixListener},
+    task::JoinHandle,
+};
+use tracing::warn;
+
+use crate::ssh;
+
+static NEXT_RELAY: AtomicUsize = AtomicUsize::new(0);
+
+/// Forwards connections from a private Unix socket to `host:port`, directly
+/// or through an SSH tunnel.
+///
+/// Postgres connections over a socket still negotiate TLS for the host name
+/// in the options. That keeps certificate verification working through a
+/// tunnel, and is how a `server_name` other than the TCP host ends up in
+/// SNI.
+pub struct Relay {
+    directory: PathBuf,
+    task: JoinHandle<()>,
+}
+
+impl Relay {
+    pub async fn start(
+        host: &str,
+        port: u16,
+        tunnel: Option<Arc<ssh::Tunnel>>,
+    ) -> Result<Self> {
+        let directory = env::temp_dir().join(format!(
+            "multi-query-{}-{}",
+            process::id(),
+            NEXT_RELAY.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&directory)?;
+        fs::set_permissions(&directory, fs::Permissions::from_mode(0o700))?;
+
+        let listener =
+            UnixListener::bind(directory.join(format!(".s.PGSQL.{}", port)))
+                .with_context(|| {
+                format!(
+                    "Failed to create relay socket in '{}'",
+                    directory.display()
+                )
+            })?;
+
+        let host = host.to_string();
+        let task = tokio::spawn(async move {
+            while let Ok((mut inbound, _)) = listener.accept().await {
+                let host = host.clone();
+                let tunnel = tunnel.clone();
+
+                tokio::spawn(async move {
+                    let outbound = match &tunnel {
+                        Some(tunnel) => tunnel.connect(&host, port).await,
+                        None => TcpStream::connect((host.as_str(), port))
+                            .await
+                            .map_err(Into::into),
+                    };
+
+                    match outbound {
+                        Ok(mut outbound) => {
+                            let _ = copy_bidirectional(
+                                &mut inbound,
+                                &mut outbound,
+                            )
+                            .await;
+                        }
+                        Err(err) => {
+                            warn!(
+                                "Failed to connect to {}:{}: {:#}",
+                                host, port, err
+                            )
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(Self { directory, task })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+}
+
+impl Drop for Relay {
+    fn drop(&mut self) {
+        self.task.abort();
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    hash::{BuildHasher, RandomState},
+    io::ErrorKind,
+    time::Duration,
+};
+
+use anyhow::{Result, bail};
+
+use crate::{RetrySettings, duration::seconds};
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_BACKOFF: f64 = 0.5;
+const DEFAULT_MAX_BACKOFF: f64 = 10.0;
+
+/// serialization_failure, deadlock_detected, admin_shutdown,
+/// crash_shutdown and cannot_connect_now. All of connection_exception
+/// (class 08) is retried as well.
+const RETRYABLE_SQLSTATES: &[&str] =
+    &["40001", "40P01", "57P01", "57P02", "57P03"];
+
+/// When and how often a failed connect or query is tried again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Policy {
+    max_attempts: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+}
+
+impl Default for Policy {
+    /// Never retries.
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            jitter: false,
+        }
+    }
+}
+
+impl Policy {
+    /// Unset values of `settings` take the defaults, three attempts with a
+    /// backoff from half a second up to ten seconds, with jitter.
+    pub fn new(settings: Option<&RetrySettings>) -> Result<Self> {
+        let Some(settings) = settings else {
+            return Ok(Self::default());
+        };
+
+        let max_attempts =
+            settings.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS);
+        if max_attempts == 0 {
+            bail!("max_attempts has to be at least 1");
+        }
+
+        Ok(Self {
+            max_attempts,
+            backoff: seconds(
+                settings.backoff.unwrap_or(DEFAULT_BACKOFF),
+                "backoff",
+            )?,
+            max_backoff: seconds(
+                settings.max_backoff.unwrap_or(DEFAULT_MAX_BACKOFF),
+                "max_backoff",
+            )?,
+            jitter: settings.jitter.unwrap_or(true),
+        })
+    }
+
+    /// How long to wait before trying again after `attempt`, counted from
+    /// 1, failed with `err`. `None` if it is not worth another try.
+    pub fn retry_after(
+        &self,
+        attempt: u32,
+        err: &anyhow::Error,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !is_transient(err) {
+            return None;
+        }
+
+        let backoff = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_backoff);
+        if !self.jitter {
+            return Some(backoff);
+        }
+
+        // Somewhere between half and all of the backoff
+        let random = RandomState::new().hash_one(attempt) >> 11;
+        let fraction = random as f64 / (1u64 << 53) as f64;
+        Some(backoff.mul_f64(0.5 + fraction / 2.0))
+    }
+}
+
+/// Failures that may well go away on their own: lost or refused
+/// connections, and the SQLSTATEs of a server that is shutting down, still
+/// starting up or had to cancel a transaction.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(err) = cause.downcast_ref::<std::io::Error>() {
+            return is_transient_io(err);
+        }
+
+        match cause.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::Io(err)) => is_transient_io(err),
+            Some(sqlx::Error::PoolTimedOut) => true,
+            Some(sqlx::Error::Database(db_err)) => {
+                db_err.code().is_some_and(|code| {
+                    code.starts_with("08")
+                        || RETRYABLE_SQLSTATES.contains(&code.as_ref())
+                })
+            }
+            _ => false,
+        }
+    })
+}
+
+fn is_transient_io(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::BrokenPipe
+            | ErrorKind::UnexpectedEof
+            | ErrorKind::TimedOut
+    )
+}
@@ -0,0 +1,159 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use serde_json::Value;
+use serde_json::json;
+use sqlx::Sqlite;
+use tokio_stream::StreamExt;
+
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow,
+};
+use sqlx::{Column, Decode, Executor, Row, TypeInfo, ValueRef};
+
+use crate::{
+    Backend, ColumnDescription, ColumnKeys, ConnectionString, JsonRow, pool,
+};
+
+/// SQLite file backend, selected by `sqlite://` URIs.
+pub struct SqliteDb {
+    pub name: String,
+    db: SqlitePool,
+    /// Whether files were loaded into it, whose BLOB columns hold JSON
+    /// text that is emitted as JSON.
+    loaded: bool,
+}
+
+impl SqliteDb {
+    pub async fn new(
+        ConnectionString { name, uri, pool: settings, .. }: ConnectionString,
+    ) -> Result<Self> {
+        let db = pool::connect(
+            settings.as_ref(),
+            pool::DEFAULT_MAX_CONNECTIONS,
+            SqliteConnectOptions::from_str(&uri)?,
+        )
+        .await?;
+
+        Ok(Self { db, name, loaded: false })
+    }
+
+    /// A private in-memory database that other backends load data into.
+    /// The pool holds on to its single connection, as closing it would
+    /// drop the data.
+    pub async fn in_memory(name: String) -> Result<Self> {
+        let db = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+
+        Ok(Self { db, name, loaded: true })
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.db
+    }
+
+    fn row_to_json(&self, row: &SqliteRow) -> JsonRow {
+        let mut json_obj = serde_json::Map::new();
+        let mut keys = ColumnKeys::default();
+
+        json_obj
+            .insert("db_name".to_string(), Value::String(self.name.clone()));
+
+        for col in row.columns() {
+            let key = keys.next(col.name());
+            let index = col.ordinal();
+
+            let json_value = match row.try_get_raw(index) {
+                Ok(raw) if !raw.is_null() => {
+                    // SQLite is dynamically typed, so the storage class of
+                    // the value decides and the declared type only refines it
+                    let declared = col.type_info().name();
+                    let storage = raw.type_info().name().to_string();
+
+                    match (storage.as_str(), declared) {
+                        ("INTEGER", "BOOLEAN") => {
+                            match <bool as Decode<Sqlite>>::decode(raw) {
+                                Ok(v) => json!(v),
+                                Err(_) => Value::Null,
+                            }
+                        }
+                        ("INTEGER", _) => {
+                            match <i64 as Decode<Sqlite>>::decode(raw) {
+                                Ok(v) => json!(v),
+                                Err(_) => Value::Null,
+                            }
+                        }
+                        ("REAL", _) => {
+                            match <f64 as Decode<Sqlite>>::decode(raw) {
+                                Ok(v) => json!(v),
+                                Err(_) => Value::Null,
+                            }
+                        }
+                        ("TEXT", "BLOB") if self.loaded => {
+                            match <String as Decode<Sqlite>>::decode(raw) {
+                                Ok(s) => serde_json::from_str(&s)
+                                    .unwrap_or(Value::String(s)),
+                                Err(_) => Value::Null,
+                            }
+                        }
+                        _ => match <String as Decode<Sqlite>>::decode(raw) {
+                            Ok(s) => Value::String(s),
+                            Err(_) => Value::Null,
+                        },
+                    }
+                }
+                _ => Value::Null,
+            };
+
+            json_obj.insert(key, json_value);
+        }
+
+        json_obj
+    }
+}
+
+#[async_trait]
+impl Backend for SqliteDb {
+    async fn connect(connection_string: ConnectionString) -> Result<Self> {
+        Self::new(connection_string).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dialects(&self) -> &'static [&'static str] {
+        &["sqlite"]
+    }
+
+    fn fetch<'a>(&'a self, query: &'a str) -> BoxStream<'a, Result<JsonRow>> {
+        Box::pin(sqlx::query(query).fetch(&self.db).map(|row| {
+            let row = row?;
+            Ok(self.row_to_json(&row))
+        }))
+    }
+
+    async fn describe(&self, query: &str) -> Result<Vec<ColumnDescription>> {
+        let description = self.db.describe(query).await?;
+
+        Ok(description
+            .columns()
+            .iter()
+            .map(|column| ColumnDescription {
+                name: column.name().to_string(),
+                type_name: column.type_info().name().to_string(),
+            })
+            .collect())
+    }
+
+    async fn close(&self) {
+        self.db.close().await
+    }
+}
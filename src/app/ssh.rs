@@ -0,0 +1,228 @@
+use std::{
+    collections::HashMap,
+    net::TcpListener,
+    process::Stdio,
+    sync::{Arc, LazyLock, Mutex, Weak},
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    process::{Child, Command},
+    sync::Mutex as AsyncMutex,
+    time::{Instant, sleep},
+};
+use tracing::debug;
+
+use crate::{KnownHostsPolicy, SshSettings};
+
+const SSH_BIN: &str = "ssh";
+
+/// How long the bastion gets to accept the login and open the proxy port.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often a tunnel is started on a fresh port when something else took
+/// the one picked for it.
+const PORT_ATTEMPTS: usize = 3;
+
+/// The tunnel through one bastion, if a target still uses it. Starting it
+/// holds the lock, so targets that need the same tunnel wait for it.
+type TunnelSlot = Arc<AsyncMutex<Weak<Tunnel>>>;
+
+/// Tunnel slots by their settings. Only weak references are kept, so a
+/// tunnel is torn down once the last target using it is dropped. Each
+/// bastion has its own slot, a slow login only holds up the targets behind
+/// that bastion.
+static TUNNELS: LazyLock<Mutex<HashMap<SshSettings, TunnelSlot>>> =
+    LazyLock::new(Default::default);
+
+/// An `ssh -D` process that serves as a SOCKS proxy through the bastion.
+/// The process is killed when the tunnel is dropped.
+pub struct Tunnel {
+    host: String,
+    port: u16,
+    _child: Child,
+}
+
+/// Returns the tunnel through the bastion in `settings`, starting it if no
+/// target uses one yet.
+pub async fn open(settings: &SshSettings) -> Result<Arc<Tunnel>> {
+    let slot = TUNNELS
+        .lock()
+        .expect("tunnel map lock poisoned")
+        .entry(settings.clone())
+        .or_default()
+        .clone();
+
+    let mut slot = slot.lock().await;
+    if let Some(tunnel) = slot.upgrade() {
+        return Ok(tunnel);
+    }
+
+    let tunnel = Arc::new(Tunnel::start(settings).await?);
+    *slot = Arc::downgrade(&tunnel);
+
+    Ok(tunnel)
+}
+
+impl Tunnel {
+    /// The proxy port is picked up front, another process may bind it before
+    /// ssh does. Then ssh gives up and it is tried again on a fresh port.
+    async fn start(settings: &SshSettings) -> Result<Self> {
+        let mut attempt = 1;
+        loop {
+            let port =
+                TcpListener::bind(("127.0.0.1", 0))?.local_addr()?.port();
+
+            match Self::start_on(settings, port).await? {
+                Some(tunnel) => return Ok(tunnel),
+                None if attempt < PORT_ATTEMPTS => {
+                    debug!("ssh {}: port {} is taken", settings.host, port);
+                    attempt += 1;
+                }
+                None => bail!(
+                    "SSH tunnel through '{}' failed: no free local port",
+                    settings.host
+                ),
+            }
+        }
+    }
+
+    /// Starts ssh with its proxy on `port`, `None` if the port is taken.
+    async fn start_on(
+        settings: &SshSettings,
+        port: u16,
+    ) -> Result<Option<Self>> {
+        let mut command = Command::new(SSH_BIN);
+        command
+            .arg("-N")
+            .arg("-D")
+            .arg(format!("127.0.0.1:{}", port))
+            .args(["-o", "ExitOnForwardFailure=yes"])
+            .args(["-o", "BatchMode=yes"]);
+        if let Some(port) = settings.port {
+            command.arg("-p").arg(port.to_string());
+        }
+        if let Some(user) = &settings.user {
+            command.arg("-l").arg(user);
+        }
+        if let Some(key) = &settings.key {
+            command.arg("-i").arg(key).args(["-o", "IdentitiesOnly=yes"]);
+        }
+        match settings.known_hosts {
+            Some(KnownHostsPolicy::Strict) => {
+                command.args(["-o", "StrictHostKeyChecking=yes"]);
+            }
+            Some(KnownHostsPolicy::AcceptNew) => {
+                command.args(["-o", "StrictHostKeyChecking=accept-new"]);
+            }
+            Some(KnownHostsPolicy::Ignore) => {
+                command
+                    .args(["-o", "StrictHostKeyChecking=no"])
+                    .args(["-o", "UserKnownHostsFile=/dev/null"]);
+            }
+            None => {}
+        }
+
+        let mut child = command
+            .arg(&settings.host)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| {
+                format!("Failed to run '{}', is OpenSSH installed?", SSH_BIN)
+            })?;
+
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            if child.try_wait()?.is_some() {
+                let output = child.wait_with_output().await?;
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                if stderr.contains("Address already in use") {
+                    return Ok(None);
+                }
+
+                bail!(
+                    "SSH tunnel through '{}' failed: {}",
+                    settings.host,
+                    stderr.trim()
+                );
+            }
+
+            if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+                break;
+            }
+
+            if Instant::now() > deadline {
+                bail!(
+                    "Timed out opening SSH tunnel through '{}'",
+                    settings.host
+                );
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+
+        // Keep reading, a full pipe would stall ssh
+        let mut stderr =
+            BufReader::new(child.stderr.take().expect("stderr is piped"))
+                .lines();
+        let host = settings.host.clone();
+        tokio::spawn(async move {
+            while let Ok(Some(line)) = stderr.next_line().await {
+                debug!("ssh {}: {}", host, line);
+            }
+        });
+
+        Ok(Some(Self { host: settings.host.clone(), port, _child: child }))
+    }
+
+    /// Opens a connection to `host:port` as seen from the bastion, with a
+    /// SOCKS5 handshake on the proxy port.
+    pub async fn connect(&self, host: &str, port: u16) -> Result<TcpStream> {
+        let unreachable = || {
+            anyhow!("{}:{} is not reachable from '{}'", host, port, self.host)
+        };
+
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).await?;
+
+        // Version 5, one method offered, no authentication
+        stream.write_all(&[5, 1, 0]).await?;
+        let mut reply = [0; 2];
+        stream.read_exact(&mut reply).await?;
+        if reply != [5, 0] {
+            bail!("Unexpected reply from the SSH tunnel to '{}'", self.host);
+        }
+
+        let host_length =
+            u8::try_from(host.len()).context("Host name is too long")?;
+        let mut request = vec![5, 1, 0, 3, host_length];
+        request.extend_from_slice(host.as_bytes());
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut reply = [0; 4];
+        stream.read_exact(&mut reply).await.map_err(|_| unreachable())?;
+        if reply[1] != 0 {
+            return Err(unreachable());
+        }
+
+        // Skip the bound address, nothing uses it
+        let address_length = match reply[3] {
+            1 => 4,
+            4 => 16,
+            3 => stream.read_u8().await? as usize,
+            _ => bail!(
+                "Unexpected reply from the SSH tunnel to '{}'",
+                self.host
+            ),
+        };
+        let mut address = vec![0; address_length + 2];
+        stream.read_exact(&mut address).await?;
+
+        Ok(stream)
+    }
+}
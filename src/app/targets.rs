@@ -0,0 +1,404 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use glob::Pattern;
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+use crate::{BackendKind, ConnectionString, Db};
+
+const REDACTED: &str = "********";
+
+/// Database the server listing connects to, it exists on every cluster.
+const MAINTENANCE_DATABASE: &str = "postgres";
+
+/// Expands connection strings that stand for several databases into one
+/// connection string per database. Everything else is passed through as is.
+pub async fn expand(
+    connection_strings: Vec<ConnectionString>,
+) -> Result<Vec<ConnectionString>> {
+    let mut templated = Vec::with_capacity(connection_strings.len());
+    for connection_string in connection_strings {
+        templated.extend(expand_template(connection_string)?);
+    }
+
+    let mut expanded = Vec::with_capacity(templated.len());
+
+    for connection_string in templated {
+        match BackendKind::from_uri(&connection_string.uri)? {
+            BackendKind::Postgres => expanded
+                .extend(expand_postgres_server(connection_string).await?),
+            BackendKind::Sqlite => {
+                expanded.extend(expand_sqlite_glob(connection_string)?)
+            }
+            _ => expanded.push(connection_string),
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// `postgres://host/tenant_*?exclude=*_test` becomes one target per database
+/// on the server matching the path pattern, or any `include` pattern, and
+/// none of the `exclude` patterns. Targets are named `<entry>/<datname>`.
+/// Template databases and ones that refuse connections are skipped.
+async fn expand_postgres_server(
+    connection_string: ConnectionString,
+) -> Result<Vec<ConnectionString>> {
+    let ConnectionString { name, uri, .. } = &connection_string;
+    let mut url = Url::parse(uri)
+        .with_context(|| format!("Invalid Postgres URI for '{}'", name))?;
+
+    let database = percent_decode_str(url.path().trim_start_matches('/'))
+        .decode_utf8_lossy()
+        .to_string();
+    if !database.contains(['*', '['])
+        || !connection_string.endpoints.is_empty()
+    {
+        return Ok(vec![connection_string]);
+    }
+
+    let mut include = vec![database];
+    let mut exclude = Vec::new();
+    let mut params = Vec::new();
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "include" => include.push(value.to_string()),
+            "exclude" => exclude.push(value.to_string()),
+            _ => params.push((key.to_string(), value.to_string())),
+        }
+    }
+    let include = compile_patterns(&include)?;
+    let exclude = compile_patterns(&exclude)?;
+
+    url.set_query(None);
+    if !params.is_empty() {
+        url.query_pairs_mut().extend_pairs(params);
+    }
+
+    set_database(&mut url, MAINTENANCE_DATABASE)?;
+    let pool = Db::connect_pool(&ConnectionString {
+        uri: url.to_string(),
+        ..connection_string.clone()
+    })
+    .await
+    .with_context(|| {
+        format!("Failed to list databases on the server of '{}'", name)
+    })?;
+
+    let databases: Vec<String> = sqlx::query_scalar(
+        "SELECT datname FROM pg_database \
+         WHERE NOT datistemplate AND datallowconn \
+         ORDER BY datname",
+    )
+    .fetch_all(&pool.db)
+    .await?;
+
+    pool.db.close().await;
+
+    let mut expanded = Vec::new();
+    for database in databases {
+        if !include.iter().any(|pattern| pattern.matches(&database))
+            || exclude.iter().any(|pattern| pattern.matches(&database))
+        {
+            continue;
+        }
+
+        set_database(&mut url, &database)?;
+        expanded.push(ConnectionString {
+            name: format!("{}/{}", name, database),
+            uri: url.to_string(),
+            ..connection_string.clone()
+        });
+    }
+
+    if expanded.is_empty() {
+        bail!("No databases on the server match '{}'", name);
+    }
+
+    Ok(expanded)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    /// The group as written, e.g. `{00..63}`, and the values it stands for.
+    Group(String, Vec<String>),
+}
+
+/// `shard-{00..63}` and `{eu,us}` in names and URIs become one connection
+/// string per combination of values. A group written the same way in the
+/// name and the URI takes the same value in both. When the name has no
+/// groups of its own, the values are appended to it as `<name>/<values>`,
+/// so the names stay the same from run to run.
+fn expand_template(
+    connection_string: ConnectionString,
+) -> Result<Vec<ConnectionString>> {
+    let name = parse_template(&connection_string.name)?;
+    let uri = parse_template(&connection_string.uri)?;
+
+    let mut groups: Vec<(&str, &[String])> = Vec::new();
+    for segment in name.iter().chain(&uri) {
+        if let Segment::Group(group, values) = segment
+            && !groups.iter().any(|(known, _)| known == group)
+        {
+            groups.push((group, values));
+        }
+    }
+
+    if groups.is_empty() {
+        return Ok(vec![connection_string]);
+    }
+
+    let name_is_templated =
+        name.iter().any(|segment| matches!(segment, Segment::Group(..)));
+
+    let mut expanded = Vec::new();
+    let mut names = HashSet::new();
+    let mut indices = vec![0; groups.len()];
+    loop {
+        let values: Vec<(&str, &str)> = groups
+            .iter()
+            .zip(&indices)
+            .map(|((group, values), index)| (*group, values[*index].as_str()))
+            .collect();
+
+        let target_name = if name_is_templated {
+            render_template(&name, &values)
+        } else {
+            let suffix = values
+                .iter()
+                .map(|(_, value)| *value)
+                .collect::<Vec<_>>()
+                .join("-");
+            format!("{}/{}", connection_string.name, suffix)
+        };
+
+        if !names.insert(target_name.clone()) {
+            bail!(
+                "'{}' expands to the name '{}' more than once",
+                connection_string.name,
+                target_name
+            );
+        }
+
+        expanded.push(ConnectionString {
+            name: target_name,
+            uri: render_template(&uri, &values),
+            ..connection_string.clone()
+        });
+
+        // Odometer over the groups, the last one changes fastest
+        let mut position = groups.len();
+        loop {
+            if position == 0 {
+                return Ok(expanded);
+            }
+            position -= 1;
+
+            indices[position] += 1;
+            if indices[position] < groups[position].1.len() {
+                break;
+            }
+            indices[position] = 0;
+        }
+    }
+}
+
+fn parse_template(template: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+        let group = &rest[start..=start + length];
+
+        match parse_group(&group[1..group.len() - 1])? {
+            Some(values) => {
+                segments.push(Segment::Literal(rest[..start].to_string()));
+                segments.push(Segment::Group(group.to_string(), values));
+            }
+            None => segments.push(Segment::Literal(
+                rest[..start + group.len()].to_string(),
+            )),
+        }
+
+        rest = &rest[start + group.len()..];
+    }
+
+    segments.push(Segment::Literal(rest.to_string()));
+
+    Ok(segments)
+}
+
+/// `00..03` gives `00`, `01`, `02`, `03`, and `eu,us` gives `eu`, `us`.
+/// Anything else is not a template group.
+fn parse_group(group: &str) -> Result<Option<Vec<String>>> {
+    if let Some((start, end)) = group.split_once("..") {
+        let (Ok(first), Ok(last)) = (start.parse::<u64>(), end.parse::<u64>())
+        else {
+            return Ok(None);
+        };
+
+        let width = if start.starts_with('0') || end.starts_with('0') {
+            start.len().max(end.len())
+        } else {
+            0
+        };
+
+        let values: Vec<u64> = if first <= last {
+            (first..=last).collect()
+        } else {
+            (last..=first).rev().collect()
+        };
+
+        return Ok(Some(
+            values
+                .into_iter()
+                .map(|value| format!("{:0width$}", value, width = width))
+                .collect(),
+        ));
+    }
+
+    if group.contains(',') {
+        let values: Vec<String> =
+            group.split(',').map(|value| value.trim().to_string()).collect();
+        if values.iter().any(String::is_empty) {
+            bail!("Empty value in template '{{{}}}'", group);
+        }
+
+        return Ok(Some(values));
+    }
+
+    Ok(None)
+}
+
+fn render_template(segments: &[Segment], values: &[(&str, &str)]) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Literal(text) => text.as_str(),
+            Segment::Group(group, _) => values
+                .iter()
+                .find(|(known, _)| known == group)
+                .map(|(_, value)| *value)
+                .unwrap_or(group),
+        })
+        .collect()
+}
+
+/// Hides the password of a URI, for printing targets.
+pub fn redact_password(uri: &str) -> String {
+    let Ok(mut url) = Url::parse(uri) else {
+        return uri.to_string();
+    };
+
+    if url.password().is_some() {
+        let _ = url.set_password(Some(REDACTED));
+    }
+
+    if url.query_pairs().any(|(key, _)| key == "password") {
+        let params: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(key, value)| {
+                let value = if key == "password" {
+                    REDACTED.to_string()
+                } else {
+                    value.to_string()
+                };
+                (key.to_string(), value)
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(params);
+    }
+
+    url.to_string()
+}
+
+pub fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern)
+                .with_context(|| format!("Invalid pattern '{}'", pattern))
+        })
+        .collect()
+}
+
+fn set_database(url: &mut Url, database: &str) -> Result<()> {
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("Postgres URI has no database path"))?
+        .clear()
+        .push(database);
+
+    Ok(())
+}
+
+/// `sqlite://sites/*.db` becomes one target per matching file, named after
+/// its path below the first directory with a pattern in it, without the
+/// extension. Files that end up with the same name are an error.
+fn expand_sqlite_glob(
+    connection_string: ConnectionString,
+) -> Result<Vec<ConnectionString>> {
+    let uri = &connection_string.uri;
+    let rest = uri.split_once("://").map(|(_, rest)| rest).unwrap_or(uri);
+    let (path, params) = match rest.split_once('?') {
+        Some((path, params)) => (path, format!("?{}", params)),
+        None => (rest, String::new()),
+    };
+
+    if !path.contains(['*', '[']) {
+        return Ok(vec![connection_string]);
+    }
+
+    let root: PathBuf = Path::new(path)
+        .components()
+        .take_while(|component| {
+            !component.as_os_str().to_string_lossy().contains(['*', '['])
+        })
+        .collect();
+
+    let mut expanded = Vec::new();
+    let mut files = HashMap::new();
+    let paths = glob::glob(path)
+        .with_context(|| format!("Invalid SQLite file pattern '{}'", path))?;
+
+    for entry in paths {
+        let file = entry?;
+        let name = file
+            .strip_prefix(&root)
+            .unwrap_or(&file)
+            .with_extension("")
+            .to_string_lossy()
+            .to_string();
+
+        if let Some(other) = files.insert(name.clone(), file.clone()) {
+            bail!(
+                "SQLite files '{}' and '{}' both map to target '{}' for '{}'",
+                other.display(),
+                file.display(),
+                name,
+                connection_string.name
+            );
+        }
+
+        expanded.push(ConnectionString {
+            name,
+            uri: format!("sqlite://{}{}", file.display(), params),
+            ..connection_string.clone()
+        });
+    }
+
+    if expanded.is_empty() {
+        bail!(
+            "No SQLite files match '{}' for '{}'",
+            path,
+            connection_string.name
+        );
+    }
+
+    Ok(expanded)
+}
@@ -0,0 +1,193 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::postgres::{PgConnectOptions, PgSslMode};
+
+use crate::{TlsMode, TlsSettings};
+
+/// Applies the `tls` block of a config entry on top of the settings from the
+/// URI. Certificate files are checked up front, so a bad path or an expired
+/// certificate is reported as such instead of as a failed handshake.
+/// `server_name` needs a relay and is left to `Db::connect_pool`.
+pub fn apply(
+    mut options: PgConnectOptions,
+    tls: &TlsSettings,
+) -> Result<PgConnectOptions> {
+    if let Some(mode) = tls.mode {
+        options = options.ssl_mode(match mode {
+            TlsMode::Disable => PgSslMode::Disable,
+            TlsMode::Allow => PgSslMode::Allow,
+            TlsMode::Prefer => PgSslMode::Prefer,
+            TlsMode::Require => PgSslMode::Require,
+            TlsMode::VerifyCa => PgSslMode::VerifyCa,
+            TlsMode::VerifyFull => PgSslMode::VerifyFull,
+        });
+    }
+
+    if let Some(path) = &tls.ca_bundle {
+        check_ca_bundle(path)?;
+        options = options.ssl_root_cert(path);
+    }
+
+    match (&tls.client_cert, &tls.client_key) {
+        (Some(cert), Some(key)) => {
+            check_client_cert(cert)?;
+            check_client_key(key)?;
+            options = options.ssl_client_cert(cert).ssl_client_key(key);
+        }
+        (None, None) => {}
+        _ => bail!("client_cert and client_key have to be set together"),
+    }
+
+    Ok(options)
+}
+
+fn read_certificates(path: &Path, what: &str) -> Result<Vec<Vec<u8>>> {
+    let pem = fs::read(path).with_context(|| {
+        format!("Failed to read {} '{}'", what, path.display())
+    })?;
+
+    let certificates = rustls_pemfile::certs(&mut pem.as_slice())
+        .map(|certificate| Ok(certificate?.to_vec()))
+        .collect::<Result<Vec<_>>>()
+        .with_context(|| {
+            format!("Invalid PEM in {} '{}'", what, path.display())
+        })?;
+
+    if certificates.is_empty() {
+        bail!("No certificates found in {} '{}'", what, path.display());
+    }
+
+    Ok(certificates)
+}
+
+/// A bundle may carry retired CAs next to current ones, it only has to have
+/// one that is valid now.
+fn check_ca_bundle(path: &Path) -> Result<()> {
+    let mut last_error = None;
+
+    for certificate in read_certificates(path, "CA bundle")? {
+        match check_validity(&certificate) {
+            Ok(()) => return Ok(()),
+            Err(err) => last_error = Some(err),
+        }
+    }
+
+    Err(last_error.expect("bundle is not empty"))
+        .with_context(|| format!("CA bundle '{}'", path.display()))
+}
+
+/// Only the leaf, the first certificate of the file, is checked.
+fn check_client_cert(path: &Path) -> Result<()> {
+    let certificates = read_certificates(path, "client certificate")?;
+
+    check_validity(&certificates[0])
+        .with_context(|| format!("Client certificate '{}'", path.display()))
+}
+
+fn check_client_key(path: &Path) -> Result<()> {
+    let pem = fs::read(path).with_context(|| {
+        format!("Failed to read client key '{}'", path.display())
+    })?;
+
+    match rustls_pemfile::private_key(&mut pem.as_slice()) {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => {
+            bail!("No private key found in client key '{}'", path.display())
+        }
+        Err(err) => Err(err).with_context(|| {
+            format!("Invalid PEM in client key '{}'", path.display())
+        }),
+    }
+}
+
+fn check_validity(certificate: &[u8]) -> Result<()> {
+    let (not_before, not_after) = validity(certificate)?;
+    let now = Utc::now();
+
+    if now > not_after {
+        bail!("expired on {}", not_after);
+    }
+    if now < not_before {
+        bail!("is not valid before {}", not_before);
+    }
+
+    Ok(())
+}
+
+/// Reads `notBefore` and `notAfter` from a DER encoded X.509 certificate.
+fn validity(certificate: &[u8]) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let (certificate, _) = expect_tlv(certificate, SEQUENCE)?;
+    let (tbs_certificate, _) = expect_tlv(certificate, SEQUENCE)?;
+
+    let mut fields = tbs_certificate;
+    if fields.first() == Some(&EXPLICIT_VERSION) {
+        fields = read_tlv(fields)?.2;
+    }
+    // serialNumber, signature and issuer come before validity
+    for _ in 0..3 {
+        fields = read_tlv(fields)?.2;
+    }
+
+    let (validity, _) = expect_tlv(fields, SEQUENCE)?;
+    let (not_before, rest) = read_time(validity)?;
+    let (not_after, _) = read_time(rest)?;
+
+    Ok((not_before, not_after))
+}
+
+const SEQUENCE: u8 = 0x30;
+const EXPLICIT_VERSION: u8 = 0xa0;
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+
+/// Splits off one DER element as tag, content and whatever follows it.
+fn read_tlv(input: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let truncated = || anyhow!("truncated certificate");
+
+    let (&tag, rest) = input.split_first().ok_or_else(truncated)?;
+    let (&first, mut rest) = rest.split_first().ok_or_else(truncated)?;
+
+    let length = if first & 0x80 == 0 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 {
+            bail!("unsupported length in certificate");
+        }
+
+        let (bytes, after) =
+            rest.split_at_checked(count).ok_or_else(truncated)?;
+        rest = after;
+        bytes.iter().fold(0, |length, byte| length << 8 | *byte as usize)
+    };
+
+    let (content, rest) =
+        rest.split_at_checked(length).ok_or_else(truncated)?;
+
+    Ok((tag, content, rest))
+}
+
+fn expect_tlv(input: &[u8], expected: u8) -> Result<(&[u8], &[u8])> {
+    let (tag, content, rest) = read_tlv(input)?;
+    if tag != expected {
+        bail!("unexpected element in certificate");
+    }
+
+    Ok((content, rest))
+}
+
+fn read_time(input: &[u8]) -> Result<(DateTime<Utc>, &[u8])> {
+    let (tag, content, rest) = read_tlv(input)?;
+    let format = match tag {
+        UTC_TIME => "%y%m%d%H%M%SZ",
+        GENERALIZED_TIME => "%Y%m%d%H%M%SZ",
+        _ => bail!("unexpected time in certificate"),
+    };
+
+    let time =
+        NaiveDateTime::parse_from_str(std::str::from_utf8(content)?, format)?;
+
+    Ok((time.and_utc(), rest))
+}
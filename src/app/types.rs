@@ -1,22 +1,240 @@
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct ConnectionString {
     pub name: String,
+    /// May be left out in config files that list `endpoints` instead.
+    #[serde(default)]
     pub uri: String,
+    /// Primary and replica URIs of one logical database, tried in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub endpoints: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint_policy: Option<EndpointPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<SshSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool: Option<PoolSettings>,
+    /// Seconds the query may run on this entry, instead of `--timeout`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetrySettings>,
+    /// How NUMERIC and DECIMAL columns are written, instead of `--numeric`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numeric: Option<NumericOutput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytea: Option<ByteaSettings>,
+    /// How inet and cidr columns are written, instead of `--inet`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inet: Option<InetOutput>,
+}
+
+/// Connection pool settings, for all entries of a config file or for one.
+/// Unset values fall back to the config file, then to one connection per
+/// target.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PoolSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_connections: Option<u32>,
+    /// Seconds to wait for a free connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acquire_timeout: Option<f64>,
+    /// Seconds after which an unused connection is closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<f64>,
+    /// Connect on first use instead of when the target is set up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lazy: Option<bool>,
+}
+
+/// Retries of transient failures, for all entries of a config file or for
+/// one. Without them nothing is retried.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RetrySettings {
+    /// Tries in total, including the first one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    /// Seconds to wait before the first retry, doubled for every next one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff: Option<f64>,
+    /// Seconds the wait between retries is capped at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_backoff: Option<f64>,
+    /// Wait a random part of the backoff, so retries don't come in waves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<bool>,
+}
+
+/// How NUMERIC and DECIMAL values are written to JSON.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NumericOutput {
+    /// A string with the exact digits and scale, `"10.50"`.
+    String,
+    /// A JSON number with the exact digits and scale, `10.50`. Readers that
+    /// parse numbers as doubles still lose precision.
+    Exact,
+    /// A JSON number rounded to the nearest double, `10.5`.
+    #[default]
+    Float,
+}
+
+/// How inet and cidr values are written to JSON.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum InetOutput {
+    /// As Postgres prints them, `"10.0.0.0/8"`.
+    #[default]
+    Text,
+    /// The address and prefix length apart,
+    /// `{"addr": "10.0.0.0", "prefix": 8}`.
+    Object,
+}
+
+/// How bytea columns are written, for all entries of a config file or for
+/// one. Unset values of an entry fall back to `--bytea` and
+/// `--bytea-max-bytes`, then to the top level of the config file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ByteaSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<ByteaEncoding>,
+    /// Values longer than this are cut short, with a marker at the end.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ByteaEncoding {
+    #[default]
+    Base64,
+    Hex,
+    /// Hex with the `\x` prefix, as Postgres prints bytea.
+    Escape,
+}
+
+/// Which of the `endpoints` of an entry may serve the query.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EndpointPolicy {
+    /// The first replica, or the first primary if no replica is up.
+    PreferReplica,
+    /// The first endpoint that is not in recovery.
+    PrimaryOnly,
+    /// The first endpoint that accepts the connection.
+    #[default]
+    Any,
+}
+
+/// TLS settings of a config entry. They take precedence over `sslmode` and
+/// friends in the URI.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TlsSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<TlsMode>,
+    /// CA certificates the server certificate is verified against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+    /// Name sent as SNI and verified against the server certificate,
+    /// instead of the host the connection goes to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TlsMode {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+/// Bastion host a config entry is reached through. Entries with the same
+/// settings share one tunnel.
+#[derive(
+    Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash,
+)]
+#[serde(deny_unknown_fields)]
+pub struct SshSettings {
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Private key to log in with, instead of the agent and default keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<PathBuf>,
+    /// How unknown bastion host keys are treated. Left to the SSH config
+    /// when not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub known_hosts: Option<KnownHostsPolicy>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum KnownHostsPolicy {
+    /// Only connect to hosts already in `known_hosts`.
+    Strict,
+    /// Add keys of new hosts, refuse changed ones.
+    AcceptNew,
+    /// Skip host key checks.
+    Ignore,
 }
 
 impl FromStr for ConnectionString {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<_> = s.splitn(2, [',', '=']).collect();
-        if parts.len() != 2 {
-            return Err("Expected format <name,uri>".into());
+        // A bare libpq `service=<name>` entry is named after the service
+        if let Some(service) = s.strip_prefix("service=")
+            && !service.contains(',')
+        {
+            return Ok(ConnectionString {
+                name: service.to_string(),
+                uri: s.to_string(),
+                ..Default::default()
+            });
         }
+
+        // Commas inside a `{eu,us}` template belong to the name
+        let mut depth = 0usize;
+        let separator = s.char_indices().find_map(|(index, c)| match c {
+            '{' => {
+                depth += 1;
+                None
+            }
+            '}' => {
+                depth = depth.saturating_sub(1);
+                None
+            }
+            ',' | '=' if depth == 0 => Some(index),
+            _ => None,
+        });
+
+        let Some(index) = separator else {
+            return Err("Expected format <name,uri>".into());
+        };
         Ok(ConnectionString {
-            name: parts[0].to_string(),
-            uri: parts[1].to_string(),
+            name: s[..index].to_string(),
+            uri: s[index + 1..].to_string(),
+            ..Default::default()
         })
     }
 }
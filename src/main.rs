@@ -16,6 +16,9 @@ pub mod config;
 #[cfg(test)]
 mod tests;
 
+/// The query ran, but not on every database.
+const PARTIAL_SUCCESS_EXIT_CODE: i32 = 2;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
@@ -41,7 +44,7 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
-    let (query, connection_strings) =
+    let (query, connection_strings, matches, config) =
         match cli::build_arguments(cli::CliOptions::required())
             .try_get_matches()
         {
@@ -58,8 +61,10 @@ async fn main() -> Result<()> {
                     .expect("required")
                     .cloned()
                     .collect();
+                let connection_strings =
+                    libpq::resolve_all(connection_strings)?;
 
-                (query, connection_strings)
+                (query, connection_strings, matches, None)
             }
             Err(_) => {
                 let matches = cli::build_arguments(cli::CliOptions {
@@ -88,17 +93,91 @@ async fn main() -> Result<()> {
                 }
 
                 debug!("Loading config from: {}", config_path.display());
-                let cfg = config::Config::load_from_file(&config_path).await?;
+                let mut cfg =
+                    config::Config::load_from_file(&config_path).await?;
+                let connection_strings =
+                    std::mem::take(&mut cfg.connection_strings);
 
-                (query, cfg.connection_strings)
+                (query, connection_strings, matches, Some(cfg))
             }
         };
 
-    let app = App::new(connection_strings, query).await?;
+    if matches.get_flag("dry_run") {
+        for target in targets::expand(connection_strings).await? {
+            let target = ConnectionString {
+                uri: targets::redact_password(&target.uri),
+                endpoints: target
+                    .endpoints
+                    .iter()
+                    .map(|endpoint| targets::redact_password(endpoint))
+                    .collect(),
+                ..target
+            };
+            println!("{}", serde_json::to_string(&target)?);
+        }
+        return Ok(());
+    }
+
+    let mut options = RunOptions {
+        skip_unreachable: matches.get_flag("skip_unreachable"),
+        on_error: match matches
+            .get_one::<String>("on_error")
+            .map(String::as_str)
+        {
+            Some("continue") => OnError::Continue,
+            _ => OnError::Abort,
+        },
+        errors: ErrorLog::open(
+            matches.get_one::<PathBuf>("error_file").map(PathBuf::as_path),
+        )?,
+        timeout: matches.get_one::<f64>("timeout").copied(),
+        deadline: matches.get_one::<f64>("deadline").copied(),
+        numeric: match matches.get_one::<String>("numeric").map(String::as_str)
+        {
+            Some("string") => Some(NumericOutput::String),
+            Some("exact") => Some(NumericOutput::Exact),
+            Some("float") => Some(NumericOutput::Float),
+            _ => None,
+        },
+        bytea: ByteaSettings {
+            encoding: match matches
+                .get_one::<String>("bytea")
+                .map(String::as_str)
+            {
+                Some("base64") => Some(ByteaEncoding::Base64),
+                Some("hex") => Some(ByteaEncoding::Hex),
+                Some("escape") => Some(ByteaEncoding::Escape),
+                _ => None,
+            },
+            max_bytes: matches.get_one::<usize>("bytea_max_bytes").copied(),
+        },
+        inet: match matches.get_one::<String>("inet").map(String::as_str) {
+            Some("text") => Some(InetOutput::Text),
+            Some("object") => Some(InetOutput::Object),
+            _ => None,
+        },
+    };
+    // Entries fall back to the flags, and the flags to the config file
+    if let Some(cfg) = config {
+        options.numeric = options.numeric.or(cfg.numeric);
+        options.inet = options.inet.or(cfg.inet);
+        if let Some(defaults) = cfg.bytea {
+            options.bytea.encoding =
+                options.bytea.encoding.or(defaults.encoding);
+            options.bytea.max_bytes =
+                options.bytea.max_bytes.or(defaults.max_bytes);
+        }
+    }
+    let app = App::new(connection_strings, query, options).await?;
 
     let result = app.execute_query_from_file().await;
+    // `process::exit` skips destructors, tunnels and relays go away here
+    drop(app);
 
     match result {
+        Ok(summary) if !summary.failed.is_empty() => {
+            process::exit(PARTIAL_SUCCESS_EXIT_CODE)
+        }
         Ok(_) => Ok(()),
 
         Err(err) => {
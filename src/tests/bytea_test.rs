@@ -0,0 +1,149 @@
+use super::utils::{
+    build_cli, create_query_file, create_test_postgres_db, parse_json_lines,
+    run_with_args,
+};
+use serde_json::{Value, json};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+const SETUP_SQL: &str = r#"
+    CREATE TABLE files (
+        id INT,
+        content BYTEA,
+        chunks BYTEA[]
+    );
+    INSERT INTO files VALUES
+        (1, '\x48656c6c6f', ARRAY['\x00ff'::BYTEA, NULL, '']),
+        (2, '', '{}'),
+        (3, NULL, NULL);
+"#;
+
+const QUERY: &str = "SELECT content, chunks FROM files ORDER BY id;";
+
+fn results(output: &Output) -> Vec<Value> {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    parse_json_lines(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[tokio::test]
+async fn test_bytea_defaults_to_base64() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+
+    let output = run_with_args(QUERY, &[], &[("files", &pg_container.uri)]);
+
+    assert_eq!(
+        results(&output),
+        vec![
+            json!({
+                "db_name": "files",
+                "content": "SGVsbG8=",
+                "chunks": ["AP8=", null, ""]
+            }),
+            json!({"db_name": "files", "content": "", "chunks": []}),
+            json!({"db_name": "files", "content": null, "chunks": null}),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_bytea_as_hex_and_escape() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+
+    let output = run_with_args(
+        QUERY,
+        &["--bytea", "hex"],
+        &[("files", &pg_container.uri)],
+    );
+    assert_eq!(
+        results(&output)[0],
+        json!({
+            "db_name": "files",
+            "content": "48656c6c6f",
+            "chunks": ["00ff", null, ""]
+        })
+    );
+
+    let output = run_with_args(
+        QUERY,
+        &["--bytea", "escape"],
+        &[("files", &pg_container.uri)],
+    );
+    assert_eq!(
+        results(&output)[0],
+        json!({
+            "db_name": "files",
+            "content": "\\x48656c6c6f",
+            "chunks": ["\\x00ff", null, "\\x"]
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_bytea_max_bytes_truncates_with_marker() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+
+    let output = run_with_args(
+        QUERY,
+        &["--bytea", "hex", "--bytea-max-bytes", "2"],
+        &[("files", &pg_container.uri)],
+    );
+
+    assert_eq!(
+        results(&output)[0],
+        json!({
+            "db_name": "files",
+            "content": "4865...(5 bytes)",
+            "chunks": ["00ff", null, ""]
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_bytea_from_config() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.json");
+    std::fs::write(
+        &config_path,
+        json!({
+            "bytea": {"encoding": "hex", "max_bytes": 2},
+            "connection_strings": [
+                {"name": "default", "uri": pg_container.uri},
+                {
+                    "name": "escaped",
+                    "uri": pg_container.uri,
+                    "bytea": {"encoding": "escape"}
+                }
+            ]
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let query_file =
+        create_query_file("SELECT content FROM files WHERE id = 1;");
+
+    // Entries take precedence over the flags, and the flags over the top
+    // level of the config, which fills in the settings left out
+    let output = Command::new(build_cli())
+        .arg("--query")
+        .arg(query_file.path())
+        .arg("--config")
+        .arg(&config_path)
+        .args(["--bytea", "base64"])
+        .output()
+        .expect("Failed to execute command");
+
+    let mut results = results(&output);
+    results.sort_by_key(|result| result["db_name"].to_string());
+    assert_eq!(
+        results,
+        vec![
+            json!({"db_name": "default", "content": "SGU=...(5 bytes)"}),
+            json!({"db_name": "escaped", "content": "\\x4865...(5 bytes)"}),
+        ]
+    );
+}
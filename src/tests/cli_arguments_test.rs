@@ -250,3 +250,26 @@ async fn test_short_flags() {
 
     assert!(output.status.success());
 }
+
+#[test]
+fn test_unsupported_connection_scheme() {
+    let cli_path = build_cli();
+    let temp_dir = TempDir::new().unwrap();
+    let query_file = temp_dir.path().join("query.sql");
+
+    std::fs::write(&query_file, "SELECT 1").unwrap();
+
+    let output = Command::new(&cli_path)
+        .args([
+            "--query",
+            query_file.to_str().unwrap(),
+            "--connection-string",
+            "test,redis://localhost:6379",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unsupported connection scheme 'redis'"));
+}
@@ -0,0 +1,202 @@
+use super::utils::{
+    build_cli, create_query_file, parse_json_lines, run_cli, run_with_args,
+    start_canned_http_server,
+};
+use serde_json::json;
+
+fn canned_clickhouse(body: &str) -> (u16, String) {
+    if body.trim() == "SELECT 1" {
+        return (200, "[\"1\"]\n[\"UInt8\"]\n[1]\n".to_string());
+    }
+
+    if body.contains("sessions") {
+        return (
+            200,
+            concat!(
+                "[\"id\",\"user\",\"id\"]\n",
+                "[\"UInt32\",\"String\",\"UInt32\"]\n",
+                "[2,\"b\",1]\n",
+            )
+            .to_string(),
+        );
+    }
+
+    if body.contains("rates") {
+        return (
+            200,
+            concat!(
+                "[\"rate\",\"big\",\"tiny\"]\n",
+                "[\"Float64\",\"Float64\",\"Float64\"]\n",
+                "[1.50,100000000000000000000,1e-7]\n",
+            )
+            .to_string(),
+        );
+    }
+
+    if body.contains("prices") {
+        return (
+            200,
+            concat!(
+                "[\"price\",\"total\",\"parts\",\"units\"]\n",
+                "[\"Decimal(10, 2)\",\"Nullable(Decimal128(4))\",",
+                "\"Array(Decimal(9, 2))\",\"Int128\"]\n",
+                "[10.50,12345678901234567890.1234,[1.00,2.50],",
+                "170141183460469231731687303715884105727]\n",
+            )
+            .to_string(),
+        );
+    }
+
+    if body.contains("events") {
+        return (
+            200,
+            concat!(
+                "[\"event\",\"count\",\"tags\"]\n",
+                "[\"String\",\"UInt64\",\"Array(String)\"]\n",
+                "[\"click\",18446744073709551615,[\"a\",\"b\"]]\n",
+                "[\"view\",3,[]]\n",
+            )
+            .to_string(),
+        );
+    }
+
+    (
+        404,
+        "Code: 60. DB::Exception: Unknown table expression identifier"
+            .to_string(),
+    )
+}
+
+#[test]
+fn test_clickhouse_rows_are_tagged_with_db_name() {
+    let address = start_canned_http_server(canned_clickhouse);
+
+    let query =
+        "SELECT event, count() AS count, tags FROM events GROUP BY ALL";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![
+        ("shard-1".to_string(), format!("clickhouse://{}/analytics", address)),
+        (
+            "shard-2".to_string(),
+            format!("clickhouse+http://default:secret@{}/analytics", address),
+        ),
+    ];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let mut results = parse_json_lines(&output);
+    results.sort_by_key(|row| {
+        (
+            row["db_name"].as_str().unwrap().to_string(),
+            row["event"].as_str().unwrap().to_string(),
+        )
+    });
+
+    let expected = vec![
+        json!({
+            "db_name": "shard-1",
+            "event": "click",
+            "count": 18446744073709551615_u64,
+            "tags": ["a", "b"]
+        }),
+        json!({"db_name": "shard-1", "event": "view", "count": 3, "tags": []}),
+        json!({
+            "db_name": "shard-2",
+            "event": "click",
+            "count": 18446744073709551615_u64,
+            "tags": ["a", "b"]
+        }),
+        json!({"db_name": "shard-2", "event": "view", "count": 3, "tags": []}),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn test_clickhouse_repeated_column_names_are_kept() {
+    let address = start_canned_http_server(canned_clickhouse);
+
+    let query_file = create_query_file(
+        "SELECT s.id, s.user, u.id FROM sessions s JOIN users u USING (user)",
+    );
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("shard-1".to_string(), format!("clickhouse://{}", address))];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    assert_eq!(
+        parse_json_lines(&output),
+        vec![json!({"db_name": "shard-1", "id": 2, "user": "b", "id_1": 1})]
+    );
+}
+
+#[test]
+fn test_clickhouse_numbers_are_written_as_doubles() {
+    let address = start_canned_http_server(canned_clickhouse);
+
+    let query_file = create_query_file("SELECT rate, big, tiny FROM rates");
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("shard-1".to_string(), format!("clickhouse://{}", address))];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    assert_eq!(
+        output.trim(),
+        r#"{"big":1e+20,"db_name":"shard-1","rate":1.5,"tiny":1e-7}"#
+    );
+}
+
+#[test]
+fn test_clickhouse_decimals_follow_numeric() {
+    let address = start_canned_http_server(canned_clickhouse);
+    let uri = format!("clickhouse://{}", address);
+
+    for (numeric, expected) in [
+        (
+            "float",
+            r#"{"db_name":"shard-1","parts":[1.0,2.5],"price":10.5,"total":1.2345678901234567e+19,"units":170141183460469231731687303715884105727}"#,
+        ),
+        (
+            "exact",
+            r#"{"db_name":"shard-1","parts":[1.00,2.50],"price":10.50,"total":12345678901234567890.1234,"units":170141183460469231731687303715884105727}"#,
+        ),
+        (
+            "string",
+            r#"{"db_name":"shard-1","parts":["1.00","2.50"],"price":"10.50","total":"12345678901234567890.1234","units":"170141183460469231731687303715884105727"}"#,
+        ),
+    ] {
+        let output = run_with_args(
+            "SELECT price, total, parts, units FROM prices",
+            &["--numeric", numeric],
+            &[("shard-1", &uri)],
+        );
+
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), expected);
+    }
+}
+
+#[test]
+fn test_clickhouse_error_response_fails() {
+    let address = start_canned_http_server(canned_clickhouse);
+
+    let query_file = create_query_file("SELECT * FROM missing");
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("shard-1".to_string(), format!("clickhouse://{}", address))];
+    let result = run_cli(&cli_path, query_file.path(), &connection_strings);
+
+    assert!(result.is_err());
+    assert!(result.unwrap_err().contains("Unknown table expression"));
+}
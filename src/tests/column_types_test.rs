@@ -1,6 +1,6 @@
 use super::utils::{
     build_cli, create_query_file, create_test_postgres_db, parse_json_lines,
-    run_cli,
+    run_cli, run_with_args,
 };
 use serde_json::json;
 
@@ -731,3 +731,413 @@ async fn test_nullable_arrays() {
 
     assert_eq!(results, expected);
 }
+
+#[tokio::test]
+async fn test_real_and_smallint_arrays_keep_their_values() {
+    let setup_sql = r#"
+        CREATE TABLE measurements (
+            id SERIAL PRIMARY KEY,
+            reading REAL,
+            readings REAL[],
+            counts SMALLINT[],
+            labels TEXT[]
+        );
+        INSERT INTO measurements (reading, readings, counts, labels)
+        VALUES (1.1, ARRAY[0.1, NULL, 2.5]::REAL[], ARRAY[1, NULL, 3]::SMALLINT[], ARRAY['a', NULL]);
+    "#;
+
+    let pg_container = create_test_postgres_db(setup_sql).await;
+
+    let query = "SELECT * FROM measurements ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("test_db".to_string(), pg_container.uri.clone())];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    let expected = vec![json!({
+        "db_name": "test_db",
+        "id": 1,
+        "reading": 1.1,
+        "readings": [0.1, null, 2.5],
+        "counts": [1, null, 3],
+        "labels": ["a", null]
+    })];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_domains_and_unsupported_types() {
+    let setup_sql = r#"
+        CREATE EXTENSION citext;
+        CREATE EXTENSION hstore;
+        CREATE DOMAIN email AS TEXT CHECK (VALUE LIKE '%@%');
+        CREATE DOMAIN quantity AS INTEGER CHECK (VALUE >= 0);
+        CREATE TABLE contacts (
+            id SERIAL PRIMARY KEY,
+            address email,
+            stock quantity,
+            nick CITEXT,
+            location POINT,
+            attrs HSTORE
+        );
+        INSERT INTO contacts (address, stock, nick, location, attrs)
+        VALUES ('alice@example.com', 3, 'Al', '(1, 2)', 'tier => gold'),
+               ('bob@example.com', 0, NULL, '(3, 4)', 'tier => free');
+    "#;
+
+    let pg_container = create_test_postgres_db(setup_sql).await;
+
+    let output = run_with_args(
+        "SELECT * FROM contacts ORDER BY id;",
+        &[],
+        &[("test_db", &pg_container.uri)],
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+
+    // Types without a decoder are null rather than failing the query
+    let expected = vec![
+        json!({
+            "db_name": "test_db",
+            "id": 1,
+            "address": "alice@example.com",
+            "stock": 3,
+            "nick": "Al",
+            "location": null,
+            "attrs": null
+        }),
+        json!({
+            "db_name": "test_db",
+            "id": 2,
+            "address": "bob@example.com",
+            "stock": 0,
+            "nick": null,
+            "location": null,
+            "attrs": null
+        }),
+    ];
+
+    assert_eq!(results, expected);
+
+    // Each of those columns is reported once, with the type it has
+    let records = parse_json_lines(&String::from_utf8_lossy(&output.stderr));
+    assert_eq!(records.len(), 2);
+    for (record, type_name) in records.iter().zip(["POINT", "hstore"]) {
+        assert_eq!(record["db_name"], "test_db");
+        assert_eq!(record["stage"], "decode");
+        assert!(
+            record["message"].as_str().unwrap().contains(&format!(
+                "failed to decode: no decoder for type {} ",
+                type_name
+            )),
+            "{}",
+            record
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_interval_money_timetz_and_text_like_types() {
+    let setup_sql = r#"
+        CREATE TABLE schedules (
+            id INT,
+            every INTERVAL,
+            fee MONEY,
+            opens TIMETZ,
+            grade "char",
+            feed XML,
+            windows INTERVAL[],
+            fees MONEY[],
+            closes TIMETZ[],
+            grades "char"[]
+        );
+        INSERT INTO schedules VALUES
+            (1, '1 year 2 mons 3 days 04:05:06.789', 1234.56,
+             '10:30:00+02', 'a', '<feed/>',
+             ARRAY['1 day'::INTERVAL, NULL], ARRAY[1::MONEY, NULL],
+             ARRAY['18:00:00.5-05:30'::TIMETZ], '{a,b}'),
+            (2, '-1 days +01:00:00', -0.07, '23:59:59+05:45:30', NULL,
+             NULL, '{}', '{}', '{}', NULL),
+            (3, '0', 0, '24:00:00+00', NULL, NULL, NULL, NULL, NULL, NULL);
+    "#;
+
+    let pg_container = create_test_postgres_db(setup_sql).await;
+
+    let query = "SELECT every, fee, opens, grade, feed, windows, fees, \
+                 closes, grades FROM schedules ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("test_db".to_string(), pg_container.uri.clone())];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    let expected = vec![
+        json!({
+            "db_name": "test_db",
+            "every": "1 year 2 mons 3 days 04:05:06.789",
+            "fee": 1234.56,
+            "opens": "10:30:00+02",
+            "grade": "a",
+            "feed": "<feed/>",
+            "windows": ["1 day", null],
+            "fees": [1.0, null],
+            "closes": ["18:00:00.5-05:30"],
+            "grades": ["a", "b"]
+        }),
+        json!({
+            "db_name": "test_db",
+            "every": "-1 days +01:00:00",
+            "fee": -0.07,
+            "opens": "23:59:59+05:45:30",
+            "grade": null,
+            "feed": null,
+            "windows": [],
+            "fees": [],
+            "closes": [],
+            "grades": null
+        }),
+        json!({
+            "db_name": "test_db",
+            "every": "00:00:00",
+            "fee": 0.0,
+            "opens": "24:00:00+00",
+            "grade": null,
+            "feed": null,
+            "windows": null,
+            "fees": null,
+            "closes": null,
+            "grades": null
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_uuid_types() {
+    let setup_sql = r#"
+        CREATE TABLE accounts (
+            id UUID PRIMARY KEY,
+            parent_id UUID,
+            member_ids UUID[]
+        );
+        INSERT INTO accounts (id, parent_id, member_ids) VALUES
+            ('A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11', NULL,
+             ARRAY['00000000-0000-0000-0000-000000000000'::UUID, NULL]),
+            ('{b1ffcd88-8d1a-4ff9-ac7e-7cc8ce491b22}',
+             'a0eebc999c0b4ef8bb6d6bb9bd380a11', '{}'),
+            ('ffffffff-ffff-ffff-ffff-ffffffffffff', NULL, NULL);
+    "#;
+
+    let pg_container = create_test_postgres_db(setup_sql).await;
+
+    let query = "SELECT * FROM accounts ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("test_db".to_string(), pg_container.uri.clone())];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    // Written in canonical form, whatever form they were inserted in
+    let expected = vec![
+        json!({
+            "db_name": "test_db",
+            "id": "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            "parent_id": null,
+            "member_ids": ["00000000-0000-0000-0000-000000000000", null]
+        }),
+        json!({
+            "db_name": "test_db",
+            "id": "b1ffcd88-8d1a-4ff9-ac7e-7cc8ce491b22",
+            "parent_id": "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            "member_ids": []
+        }),
+        json!({
+            "db_name": "test_db",
+            "id": "ffffffff-ffff-ffff-ffff-ffffffffffff",
+            "parent_id": null,
+            "member_ids": null
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_uuid_functions() {
+    let pg_container = create_test_postgres_db("").await;
+
+    let query = "SELECT gen_random_uuid() AS generated, \
+                 array_agg(gen_random_uuid()) AS batch \
+                 FROM generate_series(1, 3);";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("test_db".to_string(), pg_container.uri.clone())];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+    assert_eq!(results.len(), 1);
+
+    let is_uuid = |value: &serde_json::Value| {
+        let text = value.as_str().unwrap();
+        let groups: Vec<usize> = text.split('-').map(str::len).collect();
+        groups == [8, 4, 4, 4, 12]
+            && text
+                .chars()
+                .all(|c| c == '-' || matches!(c, '0'..='9' | 'a'..='f'))
+    };
+    assert!(is_uuid(&results[0]["generated"]), "{}", results[0]);
+    let batch = results[0]["batch"].as_array().unwrap();
+    assert_eq!(batch.len(), 3);
+    assert!(batch.iter().all(is_uuid), "{}", results[0]);
+}
+
+const NETWORK_SETUP_SQL: &str = r#"
+    CREATE TABLE hosts (
+        id SERIAL PRIMARY KEY,
+        address INET,
+        network CIDR,
+        mac MACADDR,
+        mac8 MACADDR8,
+        aliases INET[],
+        routes CIDR[],
+        nics MACADDR[]
+    );
+    INSERT INTO hosts (address, network, mac, mac8, aliases, routes, nics)
+    VALUES
+        ('10.0.0.1', '10.0.0.1/32', '08:00:2B:01:02:03',
+         '08:00:2b:01:02:03:04:05', ARRAY['192.168.1.5/24'::INET, NULL],
+         ARRAY['10.0.0.0/8'::CIDR, '2001:db8::/32'],
+         ARRAY['08-00-2b-01-02-03'::MACADDR, NULL]),
+        ('2001:db8::1', '2001:db8:0:1::/64', NULL, NULL, '{}', NULL, NULL),
+        (NULL, NULL, NULL, NULL, NULL, NULL, NULL);
+"#;
+
+#[tokio::test]
+async fn test_network_address_types() {
+    let pg_container = create_test_postgres_db(NETWORK_SETUP_SQL).await;
+
+    let query = "SELECT * FROM hosts ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("test_db".to_string(), pg_container.uri.clone())];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    // Written the way Postgres prints them
+    let expected = vec![
+        json!({
+            "db_name": "test_db",
+            "id": 1,
+            "address": "10.0.0.1",
+            "network": "10.0.0.1/32",
+            "mac": "08:00:2b:01:02:03",
+            "mac8": "08:00:2b:01:02:03:04:05",
+            "aliases": ["192.168.1.5/24", null],
+            "routes": ["10.0.0.0/8", "2001:db8::/32"],
+            "nics": ["08:00:2b:01:02:03", null]
+        }),
+        json!({
+            "db_name": "test_db",
+            "id": 2,
+            "address": "2001:db8::1",
+            "network": "2001:db8:0:1::/64",
+            "mac": null,
+            "mac8": null,
+            "aliases": [],
+            "routes": null,
+            "nics": null
+        }),
+        json!({
+            "db_name": "test_db",
+            "id": 3,
+            "address": null,
+            "network": null,
+            "mac": null,
+            "mac8": null,
+            "aliases": null,
+            "routes": null,
+            "nics": null
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_network_address_types_as_objects() {
+    let pg_container = create_test_postgres_db(NETWORK_SETUP_SQL).await;
+
+    let query =
+        "SELECT address, network, aliases, mac FROM hosts ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let output = std::process::Command::new(build_cli())
+        .arg("--query")
+        .arg(query_file.path())
+        .arg("--connection-string")
+        .arg(format!("test_db,{}", pg_container.uri))
+        .args(["--inet", "object"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+
+    // MAC addresses have no prefix, they stay text
+    let expected = vec![
+        json!({
+            "db_name": "test_db",
+            "address": {"addr": "10.0.0.1", "prefix": 32},
+            "network": {"addr": "10.0.0.1", "prefix": 32},
+            "aliases": [{"addr": "192.168.1.5", "prefix": 24}, null],
+            "mac": "08:00:2b:01:02:03"
+        }),
+        json!({
+            "db_name": "test_db",
+            "address": {"addr": "2001:db8::1", "prefix": 128},
+            "network": {"addr": "2001:db8:0:1::", "prefix": 64},
+            "aliases": [],
+            "mac": null
+        }),
+        json!({
+            "db_name": "test_db",
+            "address": null,
+            "network": null,
+            "aliases": null,
+            "mac": null
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
@@ -0,0 +1,228 @@
+use super::utils::{
+    build_cli, config_command, create_query_file, create_test_duckdb_db,
+    parse_json_lines, run_cli, run_with_args,
+};
+use serde_json::json;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+/// Stands in for the `duckdb` CLI: answers the connection probe, and fails
+/// any other statement with far more on stderr than a pipe buffers.
+const FAKE_DUCKDB: &str = r#"#!/usr/bin/env python3
+import sys
+
+if sys.argv[-1] == "SELECT 1":
+    print("1")
+    sys.exit(0)
+
+sys.stderr.write("Error: " + "x" * 1_000_000 + "\n")
+sys.exit(1)
+"#;
+
+#[test]
+fn test_duckdb_scalar_types() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("extract.duckdb");
+
+    let setup_sql = r#"
+        CREATE TABLE orders (
+            id INTEGER,
+            customer VARCHAR,
+            total DECIMAL(10,2),
+            paid BOOLEAN,
+            big HUGEINT
+        );
+        INSERT INTO orders VALUES (1, 'Alice', 19.99, true, 12345678901234);
+        INSERT INTO orders VALUES (2, 'Bob', NULL, false, NULL);
+    "#;
+    create_test_duckdb_db(&db_path, setup_sql);
+
+    let query = "SELECT * FROM orders ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "warehouse".to_string(),
+        format!("duckdb://{}", db_path.display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    let expected = vec![
+        json!({
+            "db_name": "warehouse",
+            "id": 1,
+            "customer": "Alice",
+            "total": 19.99,
+            "paid": true,
+            "big": 12345678901234_i64
+        }),
+        json!({
+            "db_name": "warehouse",
+            "id": 2,
+            "customer": "Bob",
+            "total": null,
+            "paid": false,
+            "big": null
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn test_duckdb_nested_types() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("nested.duckdb");
+    create_test_duckdb_db(&db_path, "CREATE TABLE placeholder (id INTEGER);");
+
+    let query = r#"
+        SELECT
+            [1, 2, 3] AS list_val,
+            {'name': 'widget', 'tags': ['a', 'b']} AS struct_val,
+            MAP {'x': 1, 'y': 2} AS map_val,
+            INTERVAL 1 DAY AS interval_val;
+    "#;
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "warehouse".to_string(),
+        format!("duckdb://{}", db_path.display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    assert_eq!(results.len(), 1);
+    let row = &results[0];
+    assert_eq!(row["db_name"], json!("warehouse"));
+    assert_eq!(row["list_val"], json!([1, 2, 3]));
+    assert_eq!(
+        row["struct_val"],
+        json!({"name": "widget", "tags": ["a", "b"]})
+    );
+    assert_eq!(row["map_val"], json!({"x": 1, "y": 2}));
+    assert!(row["interval_val"].is_string());
+}
+
+#[test]
+fn test_duckdb_numbers_follow_numeric() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("wide.duckdb");
+    create_test_duckdb_db(&db_path, "CREATE TABLE placeholder (id INTEGER);");
+    let uri = format!("duckdb://{}", db_path.display());
+
+    let query = r#"
+        SELECT
+            10.5::DECIMAL(10,2) AS price,
+            '12345678901234567890.1234'::DECIMAL(38,4) AS total,
+            [1, 2.5]::DECIMAL(9,2)[] AS parts,
+            170141183460469231731687303715884105727::HUGEINT AS units;
+    "#;
+
+    for (numeric, expected) in [
+        (
+            "float",
+            r#"{"db_name":"warehouse","parts":[1.0,2.5],"price":10.5,"total":1.2345678901234567e+19,"units":170141183460469231731687303715884105727}"#,
+        ),
+        (
+            "exact",
+            r#"{"db_name":"warehouse","parts":[1.00,2.50],"price":10.50,"total":12345678901234567890.1234,"units":170141183460469231731687303715884105727}"#,
+        ),
+        (
+            "string",
+            r#"{"db_name":"warehouse","parts":["1.00","2.50"],"price":"10.50","total":"12345678901234567890.1234","units":"170141183460469231731687303715884105727"}"#,
+        ),
+    ] {
+        let output = run_with_args(
+            query,
+            &["--numeric", numeric],
+            &[("warehouse", &uri)],
+        );
+
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), expected);
+    }
+}
+
+#[test]
+fn test_duckdb_query_error_is_reported() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("empty.duckdb");
+    create_test_duckdb_db(&db_path, "CREATE TABLE placeholder (id INTEGER);");
+
+    let query_file = create_query_file("SELECT * FROM missing_table;");
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "warehouse".to_string(),
+        format!("duckdb://{}", db_path.display()),
+    )];
+    let result = run_cli(&cli_path, query_file.path(), &connection_strings);
+
+    assert!(result.is_err());
+    assert!(result.unwrap_err().contains("missing_table"));
+}
+
+#[test]
+fn test_duckdb_missing_file_is_not_created() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("mistyped.duckdb");
+
+    let query_file = create_query_file("SELECT 1 AS one;");
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "warehouse".to_string(),
+        format!("duckdb://{}", db_path.display()),
+    )];
+    let result = run_cli(&cli_path, query_file.path(), &connection_strings);
+
+    assert!(result.unwrap_err().contains("does not exist"));
+    assert!(!db_path.exists());
+}
+
+#[test]
+fn test_duckdb_long_error_is_reported() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("extract.duckdb");
+    std::fs::write(&db_path, "").unwrap();
+
+    let bin_path = temp_dir.path().join("duckdb");
+    std::fs::write(&bin_path, FAKE_DUCKDB).unwrap();
+    std::fs::set_permissions(
+        &bin_path,
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    let path = format!(
+        "{}:{}",
+        temp_dir.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let output = config_command(
+        &temp_dir,
+        "SELECT * FROM orders;",
+        json!({
+            "connection_strings": [{
+                "name": "warehouse",
+                "uri": format!("duckdb://{}", db_path.display())
+            }]
+        }),
+    )
+    .env("PATH", path)
+    .output()
+    .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Error: xxx"));
+}
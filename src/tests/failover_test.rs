@@ -0,0 +1,141 @@
+use super::utils::{
+    create_test_postgres_db, parse_json_lines, run_with_config, unreachable,
+};
+use serde_json::json;
+use tempfile::TempDir;
+use url::Url;
+
+const SETUP_SQL: &str = r#"
+    CREATE TABLE orders (id INT);
+    INSERT INTO orders VALUES (1);
+"#;
+
+const QUERY: &str = "SELECT id FROM orders;";
+
+fn redacted(uri: &str) -> String {
+    let mut url = Url::parse(uri).unwrap();
+    url.set_password(Some("********")).unwrap();
+    url.to_string()
+}
+
+#[tokio::test]
+async fn test_failover_to_next_endpoint() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run_with_config(
+        &temp_dir,
+        QUERY,
+        json!({
+            "connection_strings": [{
+                "name": "orders",
+                "endpoints": [
+                    unreachable(&pg_container.uri),
+                    pg_container.uri
+                ]
+            }]
+        }),
+    );
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+
+    let expected = vec![json!({
+        "db_name": "orders",
+        "endpoint": redacted(&pg_container.uri),
+        "id": 1
+    })];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_prefer_replica_falls_back_to_first_primary() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    let first = format!("{}?application_name=first", pg_container.uri);
+    let second = format!("{}?application_name=second", pg_container.uri);
+
+    let output = run_with_config(
+        &temp_dir,
+        QUERY,
+        json!({
+            "connection_strings": [{
+                "name": "orders",
+                "endpoints": [first, second],
+                "endpoint_policy": "prefer-replica"
+            }]
+        }),
+    );
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["endpoint"], json!(redacted(&first)));
+}
+
+#[tokio::test]
+async fn test_no_usable_endpoint_fails() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run_with_config(
+        &temp_dir,
+        QUERY,
+        json!({
+            "connection_strings": [{
+                "name": "orders",
+                "endpoints": [unreachable(&pg_container.uri)],
+                "endpoint_policy": "primary-only"
+            }]
+        }),
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("No endpoint of 'orders' could be used"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains(&redacted(&unreachable(&pg_container.uri))),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_uri_and_endpoints_are_exclusive() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run_with_config(
+        &temp_dir,
+        QUERY,
+        json!({
+            "connection_strings": [{
+                "name": "orders",
+                "uri": "postgres://primary/orders",
+                "endpoints": ["postgres://replica/orders"]
+            }]
+        }),
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("'orders' has both a uri and endpoints"),
+        "{}",
+        stderr
+    );
+}
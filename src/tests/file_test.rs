@@ -0,0 +1,326 @@
+use super::utils::{build_cli, create_query_file, parse_json_lines, run_cli};
+use serde_json::json;
+use tempfile::TempDir;
+
+#[test]
+fn test_query_csv_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("payments.csv");
+    std::fs::write(
+        &csv_path,
+        "id,customer,amount,settled\n\
+         1,Alice,19.99,true\n\
+         2,Bob,5,false\n\
+         3,\"Smith, Jane\",,true\n",
+    )
+    .unwrap();
+
+    let query = "SELECT * FROM payments WHERE settled ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "partner".to_string(),
+        format!("file://{}", csv_path.display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    let expected = vec![
+        json!({
+            "db_name": "partner",
+            "id": 1,
+            "customer": "Alice",
+            "amount": 19.99,
+            "settled": true
+        }),
+        json!({
+            "db_name": "partner",
+            "id": 3,
+            "customer": "Smith, Jane",
+            "amount": null,
+            "settled": true
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn test_csv_keeps_leading_zeros_and_signs() {
+    let temp_dir = TempDir::new().unwrap();
+    let csv_path = temp_dir.path().join("branches.csv");
+    std::fs::write(
+        &csv_path,
+        "id,zip,phone,balance\n\
+         1,007,+4930,0\n\
+         2,00123,030,-0.5\n",
+    )
+    .unwrap();
+
+    let query_file = create_query_file("SELECT * FROM branches ORDER BY id;");
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "partner".to_string(),
+        format!("file://{}", csv_path.display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    assert_eq!(
+        parse_json_lines(&output),
+        vec![
+            json!({
+                "db_name": "partner",
+                "id": 1,
+                "zip": "007",
+                "phone": "+4930",
+                "balance": 0.0
+            }),
+            json!({
+                "db_name": "partner",
+                "id": 2,
+                "zip": "00123",
+                "phone": "030",
+                "balance": -0.5
+            }),
+        ]
+    );
+}
+
+#[test]
+fn test_query_json_lines_file_with_nested_values() {
+    let temp_dir = TempDir::new().unwrap();
+    let jsonl_path = temp_dir.path().join("events.jsonl");
+    std::fs::write(
+        &jsonl_path,
+        concat!(
+            "{\"id\": 1, \"kind\": \"signup\", \"meta\": {\"plan\": \"pro\"}}\n",
+            "\n",
+            "{\"id\": 2, \"kind\": \"login\", \"tags\": [\"web\", \"eu\"]}\n",
+        ),
+    )
+    .unwrap();
+
+    let query = "SELECT id, kind, meta, tags FROM events ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "partner".to_string(),
+        format!("file://{}", jsonl_path.display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    let expected = vec![
+        json!({
+            "db_name": "partner",
+            "id": 1,
+            "kind": "signup",
+            "meta": {"plan": "pro"},
+            "tags": null
+        }),
+        json!({
+            "db_name": "partner",
+            "id": 2,
+            "kind": "login",
+            "meta": null,
+            "tags": ["web", "eu"]
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn test_json_lines_column_types_widen_across_rows() {
+    let temp_dir = TempDir::new().unwrap();
+    let jsonl_path = temp_dir.path().join("orders.jsonl");
+    std::fs::write(
+        &jsonl_path,
+        concat!(
+            "{\"id\": 1, \"total\": 10, \"code\": 7}\n",
+            "{\"id\": 2, \"total\": 2.5, \"code\": \"A7\"}\n",
+            "{\"id\": 3, \"total\": null, \"code\": true}\n",
+        ),
+    )
+    .unwrap();
+
+    let query = "SELECT id, total, code, typeof(code) AS code_type \
+                 FROM orders ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "partner".to_string(),
+        format!("file://{}", jsonl_path.display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    let expected = vec![
+        json!({
+            "db_name": "partner",
+            "id": 1,
+            "total": 10.0,
+            "code": "7",
+            "code_type": "text"
+        }),
+        json!({
+            "db_name": "partner",
+            "id": 2,
+            "total": 2.5,
+            "code": "A7",
+            "code_type": "text"
+        }),
+        json!({
+            "db_name": "partner",
+            "id": 3,
+            "total": null,
+            "code": "true",
+            "code_type": "text"
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn test_json_lines_numbers_are_read_as_doubles() {
+    let temp_dir = TempDir::new().unwrap();
+    let jsonl_path = temp_dir.path().join("scores.jsonl");
+    std::fs::write(
+        &jsonl_path,
+        concat!(
+            "{\"id\": 1, \"score\": 1.50, \"meta\": {\"weight\": 2.50}}\n",
+            "{\"id\": 2, \"score\": \"n/a\", \"meta\": {\"weight\": 1e2}}\n",
+        ),
+    )
+    .unwrap();
+
+    let query_file =
+        create_query_file("SELECT id, score, meta FROM scores ORDER BY id;");
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "partner".to_string(),
+        format!("file://{}", jsonl_path.display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    assert_eq!(
+        output.lines().collect::<Vec<_>>(),
+        vec![
+            r#"{"db_name":"partner","id":1,"meta":{"weight":2.5},"score":"1.5"}"#,
+            r#"{"db_name":"partner","id":2,"meta":{"weight":100.0},"score":"n/a"}"#,
+        ]
+    );
+}
+
+#[test]
+fn test_glob_loads_every_file_as_a_table() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("customers.csv"),
+        "id,name\n1,Alice\n2,Bob\n",
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join("orders.ndjson"),
+        "{\"customer_id\": 1, \"total\": 10}\n{\"customer_id\": 1, \"total\": 5}\n",
+    )
+    .unwrap();
+
+    let query = r#"
+        SELECT c.name, SUM(o.total) AS total
+        FROM customers c JOIN orders o ON o.customer_id = c.id
+        GROUP BY c.name;
+    "#;
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "exports".to_string(),
+        format!("file://{}/*", temp_dir.path().display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    assert_eq!(
+        results,
+        vec![json!({"db_name": "exports", "name": "Alice", "total": 15})]
+    );
+}
+
+#[test]
+fn test_json_columns_keep_their_type_under_aliases() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("events.jsonl"),
+        "{\"id\": 1, \"meta\": {\"plan\": \"pro\"}}\n",
+    )
+    .unwrap();
+    std::fs::write(temp_dir.path().join("notes.csv"), "id,meta\n1,[1]\n")
+        .unwrap();
+
+    // Only the JSON column of `events` is decoded, not the text column of
+    // the same name in `notes`
+    let query = r#"
+        SELECT e.meta AS plan, n.meta
+        FROM events e JOIN notes n USING (id);
+    "#;
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "exports".to_string(),
+        format!("file://{}/*", temp_dir.path().display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    assert_eq!(
+        parse_json_lines(&output),
+        vec![json!({
+            "db_name": "exports",
+            "plan": {"plan": "pro"},
+            "meta": "[1]"
+        })]
+    );
+}
+
+#[test]
+fn test_unsupported_file_extension_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let query_file = create_query_file("SELECT 1;");
+    let cli_path = build_cli();
+
+    // `.json` files are usually one document rather than JSON lines
+    for (file_name, content) in
+        [("report.xlsx", "binary"), ("report.json", "[{\"id\": 1}]")]
+    {
+        let path = temp_dir.path().join(file_name);
+        std::fs::write(&path, content).unwrap();
+
+        let connection_strings = vec![(
+            "partner".to_string(),
+            format!("file://{}", path.display()),
+        )];
+        let result =
+            run_cli(&cli_path, query_file.path(), &connection_strings);
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Unsupported file"));
+    }
+}
@@ -0,0 +1,216 @@
+use super::utils::{
+    build_cli, create_query_file, create_test_postgres_db, parse_json_lines,
+};
+use serde_json::json;
+use std::process::Command;
+use tempfile::TempDir;
+use url::Url;
+
+struct Server {
+    host: String,
+    port: u16,
+    user: String,
+    password: String,
+    dbname: String,
+}
+
+fn server(uri: &str) -> Server {
+    let url = Url::parse(uri).expect("Invalid container URI");
+
+    Server {
+        host: url.host_str().unwrap().to_string(),
+        port: url.port().unwrap(),
+        user: url.username().to_string(),
+        password: url.password().unwrap_or_default().to_string(),
+        dbname: url.path().trim_start_matches('/').to_string(),
+    }
+}
+
+const SETUP_SQL: &str = r#"
+    CREATE TABLE settings (key TEXT, value TEXT);
+    INSERT INTO settings VALUES ('region', 'eu-west');
+"#;
+
+const QUERY: &str = "SELECT key, value FROM settings;";
+
+#[tokio::test]
+async fn test_service_entries_from_pg_service_conf() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let server = server(&pg_container.uri);
+
+    let temp_dir = TempDir::new().unwrap();
+    let service_file = temp_dir.path().join("pg_service.conf");
+    std::fs::write(
+        &service_file,
+        format!(
+            "# managed by the DBA team\n\
+             [reporting]\n\
+             host={}\n\
+             port={}\n\
+             user={}\n\
+             password={}\n\
+             dbname={}\n\
+             \n\
+             [unreachable]\n\
+             host=nowhere.invalid\n",
+            server.host,
+            server.port,
+            server.user,
+            server.password,
+            server.dbname
+        ),
+    )
+    .unwrap();
+
+    let query_file = create_query_file(QUERY);
+
+    let cli_path = build_cli();
+    let output = Command::new(&cli_path)
+        .env("PGSERVICEFILE", &service_file)
+        .arg("--query")
+        .arg(query_file.path())
+        .args([
+            "--connection-string",
+            "service=reporting",
+            "--connection-string",
+            "named,service=reporting",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut results =
+        parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    results.sort_by_key(|row| row["db_name"].to_string());
+
+    let expected = vec![
+        json!({"db_name": "named", "key": "region", "value": "eu-west"}),
+        json!({"db_name": "reporting", "key": "region", "value": "eu-west"}),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_service_entries_in_config_file() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let server = server(&pg_container.uri);
+
+    let temp_dir = TempDir::new().unwrap();
+    let service_file = temp_dir.path().join("pg_service.conf");
+    std::fs::write(
+        &service_file,
+        format!(
+            "[reporting]\nhost={}\nport={}\nuser={}\npassword={}\n",
+            server.host, server.port, server.user, server.password
+        ),
+    )
+    .unwrap();
+
+    // The entry names the database, the service file supplies the rest
+    let config_path = temp_dir.path().join("config.json");
+    let config = json!({
+        "connection_strings": [{
+            "name": "from_config",
+            "uri": format!("service=reporting dbname='{}'", server.dbname)
+        }]
+    });
+    std::fs::write(&config_path, config.to_string()).unwrap();
+
+    let query_file = create_query_file(QUERY);
+
+    let cli_path = build_cli();
+    let output = Command::new(&cli_path)
+        .env("PGSERVICEFILE", &service_file)
+        .arg("--query")
+        .arg(query_file.path())
+        .arg("--config")
+        .arg(&config_path)
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+
+    let expected = vec![
+        json!({"db_name": "from_config", "key": "region", "value": "eu-west"}),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_pgpass_and_environment_fill_in_missing_settings() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let server = server(&pg_container.uri);
+
+    let temp_dir = TempDir::new().unwrap();
+    let pgpass = temp_dir.path().join("pgpass");
+    std::fs::write(
+        &pgpass,
+        format!(
+            "{}:{}:*:{}:{}\n",
+            server.host, server.port, server.user, server.password
+        ),
+    )
+    .unwrap();
+
+    let query_file = create_query_file(QUERY);
+
+    let cli_path = build_cli();
+    let output = Command::new(&cli_path)
+        .env("PGPASSFILE", &pgpass)
+        .env("PGHOST", &server.host)
+        .env("PGPORT", server.port.to_string())
+        .env("PGUSER", &server.user)
+        .env_remove("PGPASSWORD")
+        .arg("--query")
+        .arg(query_file.path())
+        .arg("--connection-string")
+        .arg(format!("env_db,postgresql:///{}", server.dbname))
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+
+    let expected = vec![
+        json!({"db_name": "env_db", "key": "region", "value": "eu-west"}),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn test_unknown_service_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let service_file = temp_dir.path().join("pg_service.conf");
+    std::fs::write(&service_file, "[reporting]\nhost=localhost\n").unwrap();
+
+    let query_file = create_query_file(QUERY);
+
+    let cli_path = build_cli();
+    let output = Command::new(&cli_path)
+        .env("PGSERVICEFILE", &service_file)
+        .arg("--query")
+        .arg(query_file.path())
+        .args(["--connection-string", "service=billing"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Definition of service 'billing' not found"));
+}
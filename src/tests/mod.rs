@@ -1,5 +1,26 @@
+mod bytea_test;
 mod cli_arguments_test;
+mod clickhouse_test;
 mod column_types_test;
 mod config_test;
+mod duckdb_test;
+mod failover_test;
+mod file_test;
+mod libpq_test;
+mod mysql_test;
+mod numeric_test;
+mod on_error_test;
+mod parquet_test;
+mod pool_test;
+mod query_variants_test;
+mod retry_test;
 mod sanity_test;
+mod server_fanout_test;
+mod sqlite_test;
+mod ssh_test;
+mod templating_test;
+mod tenant_schema_test;
+mod timeout_test;
+mod tls_test;
+mod unreachable_test;
 mod utils;
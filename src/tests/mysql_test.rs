@@ -0,0 +1,120 @@
+use super::utils::{
+    build_cli, create_query_file, create_test_mysql_db,
+    create_test_postgres_db, parse_json_lines, run_cli,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_mysql_column_types() {
+    let setup_sql = r#"
+        CREATE TABLE items (
+            id INT AUTO_INCREMENT PRIMARY KEY,
+            name VARCHAR(100) NOT NULL,
+            active TINYINT(1) NOT NULL,
+            quantity INT UNSIGNED NOT NULL,
+            big_count BIGINT UNSIGNED NOT NULL,
+            price DECIMAL(10,2) NOT NULL,
+            ratio FLOAT,
+            created_at DATETIME NOT NULL,
+            released DATE,
+            attributes JSON
+        );
+        INSERT INTO items
+            (name, active, quantity, big_count, price, ratio, created_at,
+             released, attributes)
+        VALUES
+            ('Widget', 1, 42, 18446744073709551615, 19.99, 0.5,
+             '2024-01-15 10:30:00', '2024-02-01', '{"color": "red"}'),
+            ('Gadget', 0, 0, 0, 5.00, NULL,
+             '2024-03-20 08:00:00', NULL, NULL);
+    "#;
+
+    let mysql_container = create_test_mysql_db(setup_sql).await;
+
+    let query = "SELECT * FROM items ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("legacy".to_string(), mysql_container.uri.clone())];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    let expected = vec![
+        json!({
+            "db_name": "legacy",
+            "id": 1,
+            "name": "Widget",
+            "active": true,
+            "quantity": 42,
+            "big_count": 18446744073709551615_u64,
+            "price": 19.99,
+            "ratio": 0.5,
+            "created_at": "2024-01-15T10:30:00",
+            "released": "2024-02-01",
+            "attributes": {"color": "red"}
+        }),
+        json!({
+            "db_name": "legacy",
+            "id": 2,
+            "name": "Gadget",
+            "active": false,
+            "quantity": 0,
+            "big_count": 0,
+            "price": 5.0,
+            "ratio": null,
+            "created_at": "2024-03-20T08:00:00",
+            "released": null,
+            "attributes": null
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_mariadb_scheme() {
+    let mysql_container = create_test_mysql_db("").await;
+
+    let query = "SELECT 1 AS one;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let uri = mysql_container.uri.replacen("mysql://", "mariadb://", 1);
+    let connection_strings = vec![("maria".to_string(), uri)];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    assert_eq!(results, vec![json!({"db_name": "maria", "one": 1})]);
+}
+
+#[tokio::test]
+async fn test_mysql_and_postgres_in_same_run() {
+    let mysql_container = create_test_mysql_db("").await;
+    let pg_container = create_test_postgres_db("").await;
+
+    let query = "SELECT 'hello' AS greeting;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![
+        ("mysql_db".to_string(), mysql_container.uri.clone()),
+        ("pg_db".to_string(), pg_container.uri.clone()),
+    ];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let mut results = parse_json_lines(&output);
+    results.sort_by_key(|row| row["db_name"].as_str().unwrap().to_string());
+
+    let expected = vec![
+        json!({"db_name": "mysql_db", "greeting": "hello"}),
+        json!({"db_name": "pg_db", "greeting": "hello"}),
+    ];
+
+    assert_eq!(results, expected);
+}
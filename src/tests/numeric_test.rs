@@ -0,0 +1,218 @@
+use super::utils::{
+    build_cli, create_query_file, create_test_postgres_db, parse_json_lines,
+    run_with_args,
+};
+use serde_json::json;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+const SETUP_SQL: &str = r#"
+    CREATE TABLE payments (
+        id INT,
+        amount NUMERIC(20, 2),
+        rate NUMERIC,
+        history NUMERIC(8, 3)[]
+    );
+    INSERT INTO payments VALUES
+        (1, 12345678901234567.89, 0.000012300, ARRAY[1.5, NULL, -0.25]),
+        (2, 10.50, 100000000000000000000, ARRAY[]::NUMERIC[]),
+        (3, -0.07, 'NaN', NULL),
+        (4, 0, -12.5, ARRAY[1000]);
+"#;
+
+const QUERY: &str = "SELECT amount, rate, history FROM payments ORDER BY id;";
+
+fn stdout_lines(output: &Output) -> Vec<String> {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_numeric_defaults_to_float() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+
+    let output = run_with_args(QUERY, &[], &[("payments", &pg_container.uri)]);
+
+    let results = parse_json_lines(&stdout_lines(&output).join("\n"));
+    assert_eq!(
+        results,
+        vec![
+            json!({
+                "db_name": "payments",
+                "amount": 12345678901234567.89,
+                "rate": 0.0000123,
+                "history": [1.5, null, -0.25]
+            }),
+            json!({
+                "db_name": "payments",
+                "amount": 10.5,
+                "rate": 1e20,
+                "history": []
+            }),
+            json!({
+                "db_name": "payments",
+                "amount": -0.07,
+                "rate": "NaN",
+                "history": null
+            }),
+            json!({
+                "db_name": "payments",
+                "amount": 0.0,
+                "rate": -12.5,
+                "history": [1000.0]
+            }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_numeric_as_string_keeps_digits_and_scale() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+
+    let output = run_with_args(
+        QUERY,
+        &["--numeric", "string"],
+        &[("payments", &pg_container.uri)],
+    );
+
+    let results = parse_json_lines(&stdout_lines(&output).join("\n"));
+    assert_eq!(
+        results,
+        vec![
+            json!({
+                "db_name": "payments",
+                "amount": "12345678901234567.89",
+                "rate": "0.000012300",
+                "history": ["1.500", null, "-0.250"]
+            }),
+            json!({
+                "db_name": "payments",
+                "amount": "10.50",
+                "rate": "100000000000000000000",
+                "history": []
+            }),
+            json!({
+                "db_name": "payments",
+                "amount": "-0.07",
+                "rate": "NaN",
+                "history": null
+            }),
+            json!({
+                "db_name": "payments",
+                "amount": "0.00",
+                "rate": "-12.5",
+                "history": ["1000.000"]
+            }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_numeric_exact_writes_numbers_verbatim() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+
+    let output = run_with_args(
+        QUERY,
+        &["--numeric", "exact"],
+        &[("payments", &pg_container.uri)],
+    );
+
+    assert_eq!(
+        stdout_lines(&output),
+        vec![
+            r#"{"amount":12345678901234567.89,"db_name":"payments","history":[1.500,null,-0.250],"rate":0.000012300}"#,
+            r#"{"amount":10.50,"db_name":"payments","history":[],"rate":100000000000000000000}"#,
+            r#"{"amount":-0.07,"db_name":"payments","history":null,"rate":"NaN"}"#,
+            r#"{"amount":0.00,"db_name":"payments","history":[1000.000],"rate":-12.5}"#,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_json_numbers_are_not_kept_exact() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+
+    // Only NUMERIC columns keep their digits, numbers in JSON documents are
+    // written the way they are with the other formats
+    let output = run_with_args(
+        r#"SELECT '{"a": 1.50, "b": 1e2}'::json AS doc,
+                  ARRAY['{"c": 2.50}'::jsonb] AS docs,
+                  0.1::float8 AS ratio;"#,
+        &["--numeric", "exact"],
+        &[("payments", &pg_container.uri)],
+    );
+
+    assert_eq!(
+        stdout_lines(&output),
+        vec![
+            r#"{"db_name":"payments","doc":{"a":1.5,"b":100.0},"docs":[{"c":2.5}],"ratio":0.1}"#
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_numeric_from_config() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.json");
+    std::fs::write(
+        &config_path,
+        json!({
+            "numeric": "string",
+            "connection_strings": [
+                {"name": "default", "uri": pg_container.uri},
+                {"name": "exact", "uri": pg_container.uri, "numeric": "exact"}
+            ]
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let query_file =
+        create_query_file("SELECT amount FROM payments WHERE id = 2;");
+
+    // Without the flag, the top level of the config applies
+    let output = Command::new(build_cli())
+        .arg("--query")
+        .arg(query_file.path())
+        .arg("--config")
+        .arg(&config_path)
+        .output()
+        .expect("Failed to execute command");
+
+    let mut lines = stdout_lines(&output);
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec![
+            r#"{"amount":"10.50","db_name":"default"}"#,
+            r#"{"amount":10.50,"db_name":"exact"}"#,
+        ]
+    );
+
+    // The flag takes precedence over the top level, but not over entries
+    let output = Command::new(build_cli())
+        .arg("--query")
+        .arg(query_file.path())
+        .arg("--config")
+        .arg(&config_path)
+        .args(["--numeric", "float"])
+        .output()
+        .expect("Failed to execute command");
+
+    let mut lines = stdout_lines(&output);
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec![
+            r#"{"amount":10.5,"db_name":"default"}"#,
+            r#"{"amount":10.50,"db_name":"exact"}"#,
+        ]
+    );
+}
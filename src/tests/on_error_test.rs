@@ -0,0 +1,133 @@
+use super::utils::{create_test_postgres_db, parse_json_lines, run_with_args};
+use serde_json::json;
+use url::Url;
+
+const SETUP_SQL: &str = r#"
+    CREATE TABLE regions (name TEXT);
+    INSERT INTO regions VALUES ('eu-west');
+"#;
+
+/// `regions` fails to read, with everything an error record can carry.
+const BROKEN_SETUP_SQL: &str = r#"
+    CREATE FUNCTION explode() RETURNS TEXT LANGUAGE plpgsql AS $$
+    BEGIN
+        RAISE EXCEPTION 'regions are being migrated'
+            USING DETAIL = 'migration 42 is running',
+                  HINT = 'try again later',
+                  ERRCODE = 'P0001';
+    END
+    $$;
+    CREATE VIEW regions AS SELECT explode() AS name;
+"#;
+
+const QUERY: &str = "SELECT name FROM regions;";
+
+#[tokio::test]
+async fn test_continue_reports_failed_databases() {
+    let up = create_test_postgres_db(SETUP_SQL).await;
+    let broken = create_test_postgres_db(BROKEN_SETUP_SQL).await;
+
+    let output = run_with_args(
+        QUERY,
+        &["--on-error", "continue"],
+        &[("up", &up.uri), ("broken", &broken.uri)],
+    );
+
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(results, vec![json!({"db_name": "up", "name": "eu-west"})]);
+
+    let errors = parse_json_lines(&String::from_utf8_lossy(&output.stderr));
+    let expected = vec![
+        json!({
+            "db_name": "broken",
+            "stage": "query",
+            "sqlstate": "P0001",
+            "message": "regions are being migrated",
+            "detail": "migration 42 is running",
+            "hint": "try again later"
+        }),
+        json!({"summary": {"succeeded": ["up"], "failed": ["broken"]}}),
+    ];
+    assert_eq!(errors, expected);
+}
+
+#[tokio::test]
+async fn test_abort_is_the_default() {
+    let up = create_test_postgres_db(SETUP_SQL).await;
+    let broken = create_test_postgres_db(BROKEN_SETUP_SQL).await;
+
+    let output =
+        run_with_args(QUERY, &[], &[("up", &up.uri), ("broken", &broken.uri)]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("regions are being migrated"), "{}", stderr);
+    assert!(parse_json_lines(&stderr).is_empty(), "{}", stderr);
+}
+
+#[tokio::test]
+async fn test_continue_fails_when_every_database_fails() {
+    let broken = create_test_postgres_db(BROKEN_SETUP_SQL).await;
+
+    let output = run_with_args(
+        QUERY,
+        &["--on-error", "continue"],
+        &[("broken", &broken.uri)],
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("The query failed on every database"),
+        "{}",
+        stderr
+    );
+
+    let errors = parse_json_lines(&stderr);
+    assert_eq!(
+        errors.last(),
+        Some(&json!({"summary": {"succeeded": [], "failed": ["broken"]}}))
+    );
+}
+
+#[tokio::test]
+async fn test_summary_includes_unreachable_databases() {
+    let up = create_test_postgres_db(SETUP_SQL).await;
+    let broken = create_test_postgres_db(BROKEN_SETUP_SQL).await;
+
+    let mut unreachable = Url::parse(&up.uri).unwrap();
+    unreachable.set_port(Some(1)).unwrap();
+
+    let output = run_with_args(
+        QUERY,
+        &["--on-error", "continue", "--skip-unreachable"],
+        &[
+            ("up", &up.uri),
+            ("broken", &broken.uri),
+            ("down", unreachable.as_str()),
+        ],
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+
+    let errors = parse_json_lines(&String::from_utf8_lossy(&output.stderr));
+    let stages: Vec<_> = errors
+        .iter()
+        .filter_map(|record| record.get("stage"))
+        .cloned()
+        .collect();
+    assert_eq!(stages, vec![json!("connect"), json!("query")]);
+    assert_eq!(
+        errors.last(),
+        Some(&json!({
+            "summary": {"succeeded": ["up"], "failed": ["down", "broken"]}
+        }))
+    );
+}
@@ -0,0 +1,274 @@
+use super::utils::{
+    build_cli, create_query_file, create_test_parquet_file, parse_json_lines,
+    run_cli, run_with_args,
+};
+use parquet::data_type::{
+    ByteArray, ByteArrayType, FixedLenByteArray, FixedLenByteArrayType,
+    Int64Type,
+};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Writes `1234567890123456.78` and `10.50` as decimals, at the top level
+/// and nested in a group.
+fn create_decimal_parquet_file(path: &Path) {
+    let schema = parse_message_type(
+        "message schema {
+            REQUIRED INT64 amount (DECIMAL(18,2));
+            REQUIRED group totals {
+                REQUIRED INT64 net (DECIMAL(18,2));
+            }
+        }",
+    )
+    .expect("Invalid Parquet schema");
+
+    let file = std::fs::File::create(path).expect("Failed to create file");
+    let mut writer = SerializedFileWriter::new(
+        file,
+        Arc::new(schema),
+        Arc::new(WriterProperties::builder().build()),
+    )
+    .expect("Failed to create Parquet writer");
+    let mut row_group = writer.next_row_group().unwrap();
+
+    for _ in 0..2 {
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[123_456_789_012_345_678, 1050], None, None)
+            .unwrap();
+        column.close().unwrap();
+    }
+
+    row_group.close().unwrap();
+    writer.close().unwrap();
+}
+
+/// Writes `±12345678901234567890123456789012345678.90`, a decimal too wide
+/// for 128 bits, next to bytes that aren't UTF-8.
+fn create_wide_parquet_file(path: &Path) {
+    let schema = parse_message_type(
+        "message schema {
+            REQUIRED FIXED_LEN_BYTE_ARRAY (17) amount (DECIMAL(40,2));
+            REQUIRED BYTE_ARRAY checksum;
+        }",
+    )
+    .expect("Invalid Parquet schema");
+
+    let file = std::fs::File::create(path).expect("Failed to create file");
+    let mut writer = SerializedFileWriter::new(
+        file,
+        Arc::new(schema),
+        Arc::new(WriterProperties::builder().build()),
+    )
+    .expect("Failed to create Parquet writer");
+    let mut row_group = writer.next_row_group().unwrap();
+
+    let amounts: [&[u8]; 2] = [
+        &[
+            3, 160, 201, 32, 117, 192, 219, 243, 184, 172, 188, 95, 150, 206,
+            63, 10, 210,
+        ],
+        &[
+            252, 95, 54, 223, 138, 63, 36, 12, 71, 83, 67, 160, 105, 49, 192,
+            245, 46,
+        ],
+    ];
+    let mut column = row_group.next_column().unwrap().unwrap();
+    column
+        .typed::<FixedLenByteArrayType>()
+        .write_batch(
+            &amounts.map(|bytes| FixedLenByteArray::from(bytes.to_vec())),
+            None,
+            None,
+        )
+        .unwrap();
+    column.close().unwrap();
+
+    let mut column = row_group.next_column().unwrap().unwrap();
+    column
+        .typed::<ByteArrayType>()
+        .write_batch(
+            &[ByteArray::from(vec![0xff, 0x00, 0x80]), ByteArray::from("ok")],
+            None,
+            None,
+        )
+        .unwrap();
+    column.close().unwrap();
+
+    row_group.close().unwrap();
+    writer.close().unwrap();
+}
+
+#[test]
+fn test_query_parquet_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("orders.parquet");
+    create_test_parquet_file(&file_path);
+
+    let query = "SELECT * FROM orders ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "lake".to_string(),
+        format!("parquet://{}", file_path.display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    let expected = vec![
+        json!({
+            "db_name": "lake",
+            "id": 1,
+            "name": "Alice",
+            "created_at": "2024-01-15T10:30:00Z",
+            "price": 19.99,
+            "tags": ["new", "sale"],
+            "dims": {"width": 3, "height": 4}
+        }),
+        json!({
+            "db_name": "lake",
+            "id": 2,
+            "name": "Bob",
+            "created_at": null,
+            "price": null,
+            "tags": null,
+            "dims": null
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn test_query_partitioned_parquet_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let dataset = temp_dir.path().join("sales");
+
+    for year in [2023, 2024] {
+        let partition = dataset.join(format!("year={}", year));
+        std::fs::create_dir_all(&partition).unwrap();
+        create_test_parquet_file(&partition.join("part-0.parquet"));
+    }
+
+    let query = r#"
+        SELECT year, COUNT(*) AS orders
+        FROM sales
+        GROUP BY year
+        ORDER BY year;
+    "#;
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("lake".to_string(), format!("parquet://{}", dataset.display()))];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    let expected = vec![
+        json!({"db_name": "lake", "year": 2023, "orders": 2}),
+        json!({"db_name": "lake", "year": 2024, "orders": 2}),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn test_empty_parquet_directory_fails() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let query_file = create_query_file("SELECT 1;");
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "lake".to_string(),
+        format!("parquet://{}", temp_dir.path().display()),
+    )];
+    let result = run_cli(&cli_path, query_file.path(), &connection_strings);
+
+    assert!(result.is_err());
+    assert!(result.unwrap_err().contains("No Parquet files found"));
+}
+
+#[test]
+fn test_parquet_decimals_follow_numeric() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("ledger.parquet");
+    create_decimal_parquet_file(&file_path);
+    let uri = format!("parquet://{}", file_path.display());
+
+    let run = |args: &[&str]| {
+        let output = run_with_args(
+            "SELECT amount, totals FROM ledger ORDER BY rowid;",
+            args,
+            &[("lake", &uri)],
+        );
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        run(&[]),
+        vec![
+            r#"{"amount":1234567890123456.8,"db_name":"lake","totals":{"net":1234567890123456.8}}"#,
+            r#"{"amount":10.5,"db_name":"lake","totals":{"net":10.5}}"#,
+        ]
+    );
+    assert_eq!(
+        run(&["--numeric", "string"]),
+        vec![
+            r#"{"amount":"1234567890123456.78","db_name":"lake","totals":{"net":"1234567890123456.78"}}"#,
+            r#"{"amount":"10.50","db_name":"lake","totals":{"net":"10.50"}}"#,
+        ]
+    );
+    assert_eq!(
+        run(&["--numeric", "exact"]),
+        vec![
+            r#"{"amount":1234567890123456.78,"db_name":"lake","totals":{"net":1234567890123456.78}}"#,
+            r#"{"amount":10.50,"db_name":"lake","totals":{"net":10.50}}"#,
+        ]
+    );
+}
+
+#[test]
+fn test_parquet_wide_decimals_and_binary_values() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("ledger.parquet");
+    create_wide_parquet_file(&file_path);
+
+    let output = run_with_args(
+        "SELECT amount, checksum FROM ledger ORDER BY rowid;",
+        &["--numeric", "string", "--bytea", "hex"],
+        &[("lake", &format!("parquet://{}", file_path.display()))],
+    );
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).lines().collect::<Vec<_>>(),
+        vec![
+            r#"{"amount":"12345678901234567890123456789012345678.90","checksum":"ff0080","db_name":"lake"}"#,
+            r#"{"amount":"-12345678901234567890123456789012345678.90","checksum":"ok","db_name":"lake"}"#,
+        ]
+    );
+}
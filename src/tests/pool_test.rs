@@ -0,0 +1,142 @@
+use super::utils::{
+    create_test_postgres_db, parse_json_lines, run_with_config,
+};
+use serde_json::json;
+use tempfile::TempDir;
+
+const SETUP_SQL: &str = r#"
+    CREATE SCHEMA tenant_a;
+    CREATE SCHEMA tenant_b;
+    CREATE SCHEMA tenant_c;
+"#;
+
+#[tokio::test]
+async fn test_config_pool_settings_apply_to_every_entry() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    // Tenant schemas share a pool, with one connection they take turns
+    let output = run_with_config(
+        &temp_dir,
+        "SELECT pg_backend_pid() AS pid;",
+        json!({
+            "pool": {"max_connections": 1, "acquire_timeout": 30},
+            "connection_strings": [{
+                "name": "app",
+                "uri": format!("{}?schemas=tenant_*", pg_container.uri)
+            }]
+        }),
+    );
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+
+    assert_eq!(results.len(), 3);
+    assert!(
+        results.iter().all(|row| row["pid"] == results[0]["pid"]),
+        "{:?}",
+        results
+    );
+}
+
+#[tokio::test]
+async fn test_entry_pool_settings_override_config() {
+    let pg_container = create_test_postgres_db("").await;
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run_with_config(
+        &temp_dir,
+        "SELECT 1 AS one;",
+        json!({
+            "pool": {"max_connections": 0},
+            "connection_strings": [{
+                "name": "overridden",
+                "uri": pg_container.uri,
+                "pool": {"max_connections": 2, "lazy": true}
+            }]
+        }),
+    );
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(results, vec![json!({"db_name": "overridden", "one": 1})]);
+
+    let output = run_with_config(
+        &temp_dir,
+        "SELECT 1 AS one;",
+        json!({
+            "pool": {"max_connections": 0},
+            "connection_strings": [{
+                "name": "inherited",
+                "uri": pg_container.uri
+            }]
+        }),
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("max_connections has to be at least 1"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_invalid_pool_settings_fail() {
+    let temp_dir = TempDir::new().unwrap();
+    let uri = format!(
+        "sqlite://{}?mode=rwc",
+        temp_dir.path().join("local.db").display()
+    );
+
+    let output = run_with_config(
+        &temp_dir,
+        "SELECT 1 AS one;",
+        json!({
+            "connection_strings": [{
+                "name": "local",
+                "uri": uri,
+                "pool": {"min_connections": 4, "max_connections": 2}
+            }]
+        }),
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "min_connections (4) is larger than max_connections (2)"
+        ),
+        "{}",
+        stderr
+    );
+
+    let output = run_with_config(
+        &temp_dir,
+        "SELECT 1 AS one;",
+        json!({
+            "connection_strings": [{
+                "name": "local",
+                "uri": uri,
+                "pool": {"acquire_timeout": -1}
+            }]
+        }),
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Invalid acquire_timeout of -1 seconds"),
+        "{}",
+        stderr
+    );
+}
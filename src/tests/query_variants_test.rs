@@ -0,0 +1,89 @@
+use super::utils::{
+    build_cli, create_test_sqlite_db, parse_json_lines, run_cli,
+};
+use serde_json::json;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_each_database_gets_most_specific_variant() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let db_path = temp_dir.path().join("edge.db");
+    create_test_sqlite_db(
+        &db_path,
+        "CREATE TABLE orders (id INTEGER PRIMARY KEY, total REAL);
+         INSERT INTO orders (total) VALUES (10.5);",
+    )
+    .await;
+
+    let csv_path = temp_dir.path().join("orders.csv");
+    std::fs::write(&csv_path, "id,total\n1,7.25\n").unwrap();
+
+    let query_path = temp_dir.path().join("report.sql");
+    std::fs::write(
+        &query_path,
+        "SELECT 'base' AS variant, total FROM orders;",
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join("report.file.sql"),
+        "SELECT 'file' AS variant, total FROM orders;",
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join("report.postgres.sql"),
+        "SELECT 'postgres' AS variant;",
+    )
+    .unwrap();
+
+    let cli_path = build_cli();
+    let connection_strings = vec![
+        ("edge".to_string(), format!("sqlite://{}", db_path.display())),
+        ("partner".to_string(), format!("file://{}", csv_path.display())),
+    ];
+    let output = run_cli(&cli_path, &query_path, &connection_strings)
+        .expect("CLI execution failed");
+
+    let mut results = parse_json_lines(&output);
+    results.sort_by_key(|row| row["db_name"].to_string());
+
+    let expected = vec![
+        json!({"db_name": "edge", "variant": "base", "total": 10.5}),
+        json!({"db_name": "partner", "variant": "file", "total": 7.25}),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_missing_variant_fails_before_running() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let db_path = temp_dir.path().join("edge.db");
+    create_test_sqlite_db(&db_path, "CREATE TABLE orders (id INTEGER);").await;
+
+    let csv_path = temp_dir.path().join("orders.csv");
+    std::fs::write(&csv_path, "id\n1\n").unwrap();
+
+    let query_path = temp_dir.path().join("report.sql");
+    std::fs::write(
+        temp_dir.path().join("report.file.sql"),
+        "SELECT id FROM orders;",
+    )
+    .unwrap();
+
+    let cli_path = build_cli();
+    let connection_strings = vec![
+        ("edge".to_string(), format!("sqlite://{}", db_path.display())),
+        ("partner".to_string(), format!("file://{}", csv_path.display())),
+    ];
+    let result = run_cli(&cli_path, &query_path, &connection_strings);
+
+    let stderr = result.expect_err("Expected the run to fail");
+    assert!(
+        stderr.contains("No query for 'edge'"),
+        "unexpected error: {}",
+        stderr
+    );
+    assert!(stderr.contains("report.sqlite.sql"));
+}
@@ -0,0 +1,191 @@
+use super::utils::{
+    create_test_postgres_db, parse_json_lines, run_with_config,
+};
+use serde_json::json;
+use std::io::copy;
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use tempfile::TempDir;
+use url::Url;
+
+/// Fails with serialization_failure the first two times, on the third
+/// `nextval` the transaction goes through.
+const FLAKY_SETUP_SQL: &str = r#"
+    CREATE SEQUENCE attempts;
+    CREATE FUNCTION flaky() RETURNS TEXT LANGUAGE plpgsql AS $$
+    BEGIN
+        IF nextval('attempts') < 3 THEN
+            RAISE EXCEPTION 'could not serialize access'
+                USING ERRCODE = '40001';
+        END IF;
+        RETURN 'ok';
+    END
+    $$;
+    CREATE FUNCTION fails_after(n INT) RETURNS INT LANGUAGE plpgsql AS $$
+    BEGIN
+        IF n > 1 THEN
+            RAISE EXCEPTION 'could not serialize access'
+                USING ERRCODE = '40001';
+        END IF;
+        RETURN n;
+    END
+    $$;
+"#;
+
+/// Forwards a free local port to `uri` once `delay` has passed, like a
+/// database coming back after a failover. Returns `uri` with that port.
+fn forward_after(uri: &str, delay: Duration) -> String {
+    let mut url = Url::parse(uri).unwrap();
+    let target =
+        format!("{}:{}", url.host_str().unwrap(), url.port().unwrap());
+
+    let port =
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    url.set_host(Some("127.0.0.1")).unwrap();
+    url.set_port(Some(port)).unwrap();
+
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+        for client in listener.incoming() {
+            let Ok(client) = client else {
+                continue;
+            };
+            let server = TcpStream::connect(&target).unwrap();
+
+            let (mut client_reader, mut server_writer) =
+                (client.try_clone().unwrap(), server.try_clone().unwrap());
+            std::thread::spawn(move || {
+                copy(&mut client_reader, &mut server_writer).ok();
+            });
+            let (mut server_reader, mut client_writer) = (server, client);
+            std::thread::spawn(move || {
+                copy(&mut server_reader, &mut client_writer).ok();
+            });
+        }
+    });
+
+    url.to_string()
+}
+
+#[tokio::test]
+async fn test_connect_is_retried() {
+    let pg_container = create_test_postgres_db("").await;
+    let temp_dir = TempDir::new().unwrap();
+
+    // sqlx waits for refused connections on its own, up to acquire_timeout
+    let output = run_with_config(
+        &temp_dir,
+        "SELECT 1 AS one;",
+        json!({
+            "pool": {"acquire_timeout": 0.5},
+            "connection_strings": [{
+                "name": "recovering",
+                "uri": forward_after(&pg_container.uri, Duration::from_secs(60))
+            }]
+        }),
+    );
+    assert!(!output.status.success());
+
+    let output = run_with_config(
+        &temp_dir,
+        "SELECT 1 AS one;",
+        json!({
+            "pool": {"acquire_timeout": 0.5},
+            "retry": {"max_attempts": 20, "backoff": 0.5, "max_backoff": 1},
+            "connection_strings": [{
+                "name": "recovering",
+                "uri": forward_after(&pg_container.uri, Duration::from_secs(3))
+            }]
+        }),
+    );
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(results, vec![json!({"db_name": "recovering", "one": 1})]);
+}
+
+#[tokio::test]
+async fn test_retryable_sqlstate_is_retried() {
+    let pg_container = create_test_postgres_db(FLAKY_SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run_with_config(
+        &temp_dir,
+        "SELECT flaky() AS result;",
+        json!({
+            "connection_strings": [{
+                "name": "flaky",
+                "uri": pg_container.uri,
+                "retry": {"max_attempts": 3, "backoff": 0.1}
+            }]
+        }),
+    );
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(results, vec![json!({"db_name": "flaky", "result": "ok"})]);
+}
+
+#[tokio::test]
+async fn test_query_is_not_retried_after_rows() {
+    let pg_container = create_test_postgres_db(FLAKY_SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run_with_config(
+        &temp_dir,
+        "SELECT fails_after(n) AS n FROM generate_series(1, 3) AS n;",
+        json!({
+            "connection_strings": [{
+                "name": "partial",
+                "uri": pg_container.uri,
+                "retry": {"max_attempts": 3, "backoff": 0.1}
+            }]
+        }),
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("could not serialize access"), "{}", stderr);
+
+    // Written once, a retry would repeat it
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(results, vec![json!({"db_name": "partial", "n": 1})]);
+}
+
+#[test]
+fn test_invalid_retry_settings_fail() {
+    let temp_dir = TempDir::new().unwrap();
+    let uri = format!(
+        "sqlite://{}?mode=rwc",
+        temp_dir.path().join("local.db").display()
+    );
+
+    let output = run_with_config(
+        &temp_dir,
+        "SELECT 1 AS one;",
+        json!({
+            "connection_strings": [{
+                "name": "local",
+                "uri": uri,
+                "retry": {"max_attempts": 0}
+            }]
+        }),
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("max_attempts has to be at least 1"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,97 @@
+use super::utils::{
+    build_cli, create_query_file, create_test_postgres_db, parse_json_lines,
+    run_cli,
+};
+use serde_json::json;
+
+fn server_uri(uri: &str, database: &str) -> String {
+    let (server, _) = uri.rsplit_once('/').expect("URI has a database");
+    format!("{}/{}", server, database)
+}
+
+#[tokio::test]
+async fn test_expand_every_matching_database_on_server() {
+    let setup_sql = r#"
+        CREATE DATABASE tenant_acme;
+        CREATE DATABASE tenant_globex;
+        CREATE DATABASE tenant_staging;
+        CREATE DATABASE tenant_archive IS_TEMPLATE true;
+        CREATE DATABASE billing;
+    "#;
+
+    let pg_container = create_test_postgres_db(setup_sql).await;
+
+    let query = "SELECT current_database() AS database;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "cluster".to_string(),
+        server_uri(&pg_container.uri, "tenant_*?exclude=*_staging"),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let mut results = parse_json_lines(&output);
+    results.sort_by_key(|row| row["db_name"].to_string());
+
+    let expected = vec![
+        json!({"db_name": "cluster/tenant_acme", "database": "tenant_acme"}),
+        json!({
+            "db_name": "cluster/tenant_globex",
+            "database": "tenant_globex"
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_include_patterns_extend_server_expansion() {
+    let setup_sql = r#"
+        CREATE DATABASE eu_orders;
+        CREATE DATABASE us_orders;
+        CREATE DATABASE us_audit;
+    "#;
+
+    let pg_container = create_test_postgres_db(setup_sql).await;
+
+    let query = "SELECT current_database() AS database;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "cluster".to_string(),
+        server_uri(&pg_container.uri, "eu_*?include=us_orders"),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let mut results = parse_json_lines(&output);
+    results.sort_by_key(|row| row["db_name"].to_string());
+
+    let expected = vec![
+        json!({"db_name": "cluster/eu_orders", "database": "eu_orders"}),
+        json!({"db_name": "cluster/us_orders", "database": "us_orders"}),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_no_matching_database_on_server_fails() {
+    let pg_container = create_test_postgres_db("").await;
+
+    let query = "SELECT 1;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "cluster".to_string(),
+        server_uri(&pg_container.uri, "nothing_here_*"),
+    )];
+    let result = run_cli(&cli_path, query_file.path(), &connection_strings);
+
+    let stderr = result.expect_err("Expected the run to fail");
+    assert!(stderr.contains("No databases on the server match 'cluster'"));
+}
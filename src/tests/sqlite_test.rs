@@ -0,0 +1,182 @@
+use super::utils::{
+    build_cli, create_query_file, create_test_sqlite_db, parse_json_lines,
+    run_cli,
+};
+use serde_json::json;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_query_single_sqlite_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("site.db");
+
+    let setup_sql = r#"
+        CREATE TABLE readings (
+            id INTEGER PRIMARY KEY,
+            sensor TEXT NOT NULL,
+            value REAL,
+            healthy BOOLEAN NOT NULL
+        );
+        INSERT INTO readings (sensor, value, healthy) VALUES ('temp', 21.5, 1);
+        INSERT INTO readings (sensor, value, healthy) VALUES ('humidity', NULL, 0);
+    "#;
+    create_test_sqlite_db(&db_path, setup_sql).await;
+
+    let query = "SELECT * FROM readings ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings =
+        vec![("edge".to_string(), format!("sqlite://{}", db_path.display()))];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let results = parse_json_lines(&output);
+
+    let expected = vec![
+        json!({
+            "db_name": "edge",
+            "id": 1,
+            "sensor": "temp",
+            "value": 21.5,
+            "healthy": true
+        }),
+        json!({
+            "db_name": "edge",
+            "id": 2,
+            "sensor": "humidity",
+            "value": null,
+            "healthy": false
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_sqlite_glob_expands_to_one_target_per_file() {
+    let temp_dir = TempDir::new().unwrap();
+
+    for (site, visitors) in [("site-a", 10), ("site-b", 20), ("site-c", 30)] {
+        let setup_sql = format!(
+            "CREATE TABLE stats (visitors INTEGER NOT NULL);
+             INSERT INTO stats (visitors) VALUES ({});",
+            visitors
+        );
+        create_test_sqlite_db(
+            &temp_dir.path().join(format!("{}.db", site)),
+            &setup_sql,
+        )
+        .await;
+    }
+    std::fs::write(temp_dir.path().join("notes.txt"), "not a database")
+        .unwrap();
+
+    let query = "SELECT visitors FROM stats;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "sites".to_string(),
+        format!("sqlite://{}/*.db", temp_dir.path().display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let mut results = parse_json_lines(&output);
+    results.sort_by_key(|row| row["db_name"].as_str().unwrap().to_string());
+
+    let expected = vec![
+        json!({"db_name": "site-a", "visitors": 10}),
+        json!({"db_name": "site-b", "visitors": 20}),
+        json!({"db_name": "site-c", "visitors": 30}),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_sqlite_glob_without_matches_fails() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let query_file = create_query_file("SELECT 1;");
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "sites".to_string(),
+        format!("sqlite://{}/*.db", temp_dir.path().display()),
+    )];
+    let result = run_cli(&cli_path, query_file.path(), &connection_strings);
+
+    assert!(result.is_err());
+    assert!(result.unwrap_err().contains("No SQLite files match"));
+}
+
+#[tokio::test]
+async fn test_sqlite_glob_names_targets_by_relative_path() {
+    let temp_dir = TempDir::new().unwrap();
+
+    for (dir, file, visitors) in
+        [("a", "shop.db", 10), ("b", "shop.sqlite", 20)]
+    {
+        std::fs::create_dir(temp_dir.path().join(dir)).unwrap();
+        let setup_sql = format!(
+            "CREATE TABLE stats (visitors INTEGER NOT NULL);
+             INSERT INTO stats (visitors) VALUES ({});",
+            visitors
+        );
+        create_test_sqlite_db(
+            &temp_dir.path().join(dir).join(file),
+            &setup_sql,
+        )
+        .await;
+    }
+
+    let query_file = create_query_file("SELECT visitors FROM stats;");
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "shops".to_string(),
+        format!("sqlite://{}/*/shop.*", temp_dir.path().display()),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let mut results = parse_json_lines(&output);
+    results.sort_by_key(|row| row["db_name"].as_str().unwrap().to_string());
+
+    let expected = vec![
+        json!({"db_name": "a/shop", "visitors": 10}),
+        json!({"db_name": "b/shop", "visitors": 20}),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_sqlite_glob_with_duplicate_names_fails() {
+    let temp_dir = TempDir::new().unwrap();
+
+    for file in ["shop.db", "shop.sqlite"] {
+        create_test_sqlite_db(
+            &temp_dir.path().join(file),
+            "CREATE TABLE stats (visitors INTEGER);",
+        )
+        .await;
+    }
+
+    let query_file = create_query_file("SELECT 1;");
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "shops".to_string(),
+        format!("sqlite://{}/shop.*", temp_dir.path().display()),
+    )];
+    let result = run_cli(&cli_path, query_file.path(), &connection_strings);
+
+    assert!(result.is_err());
+    assert!(
+        result.unwrap_err().contains("both map to target 'shop'"),
+        "duplicate names should be reported"
+    );
+}
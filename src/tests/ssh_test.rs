@@ -0,0 +1,245 @@
+use super::utils::{
+    config_command, create_test_postgres_db, parse_json_lines,
+};
+use serde_json::{Value, json};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Output;
+use tempfile::TempDir;
+
+/// Stands in for `ssh -N -D <address>` and the bastion behind it: a SOCKS5
+/// proxy on the given address that connects from the local machine. Every
+/// invocation is appended to `ssh.log` next to the script. Through
+/// `busy.example` the first proxy port is always taken.
+const FAKE_SSH: &str = r#"#!/usr/bin/env python3
+import os, socket, sys, threading
+
+args = sys.argv[1:]
+with open(os.path.join(os.path.dirname(sys.argv[0]), "ssh.log"), "a") as log:
+    log.write(" ".join(args) + "\n")
+
+if args[-1] == "denied.example":
+    sys.stderr.write("Permission denied (publickey).\n")
+    sys.exit(255)
+
+host, port = args[args.index("-D") + 1].rsplit(":", 1)
+
+busy = os.path.join(os.path.dirname(sys.argv[0]), "busy")
+if args[-1] == "busy.example" and not os.path.exists(busy):
+    open(busy, "w").close()
+    sys.stderr.write(
+        f"bind [{host}]:{port}: Address already in use\n"
+        "Could not request local forwarding.\n"
+    )
+    sys.exit(255)
+server = socket.create_server((host, int(port)))
+
+def read(sock, n):
+    data = b""
+    while len(data) < n:
+        chunk = sock.recv(n - len(data))
+        if not chunk:
+            raise OSError("closed")
+        data += chunk
+    return data
+
+def pipe(source, target):
+    try:
+        while data := source.recv(65536):
+            target.sendall(data)
+    except OSError:
+        pass
+    for sock in (source, target):
+        try:
+            sock.shutdown(socket.SHUT_RDWR)
+        except OSError:
+            pass
+
+def handle(client):
+    try:
+        methods = read(client, 2)[1]
+        read(client, methods)
+        client.sendall(b"\x05\x00")
+        read(client, 3)
+        length = read(client, 2)[1]
+        name = read(client, length).decode()
+        port = int.from_bytes(read(client, 2), "big")
+        target = socket.create_connection((name, port))
+        client.sendall(b"\x05\x00\x00\x01\x00\x00\x00\x00\x00\x00")
+    except OSError:
+        client.close()
+        return
+    threading.Thread(target=pipe, args=(client, target), daemon=True).start()
+    pipe(target, client)
+
+while True:
+    client, _ = server.accept()
+    threading.Thread(target=handle, args=(client,), daemon=True).start()
+"#;
+
+const SETUP_SQL: &str = r#"
+    CREATE TABLE regions (name TEXT);
+    INSERT INTO regions VALUES ('eu-west');
+"#;
+
+fn install_fake_ssh(dir: &Path) {
+    let path = dir.join("ssh");
+    std::fs::write(&path, FAKE_SSH).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+        .unwrap();
+}
+
+fn run_with_fake_ssh(temp_dir: &TempDir, config: Value) -> Output {
+    install_fake_ssh(temp_dir.path());
+
+    let path = format!(
+        "{}:{}",
+        temp_dir.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+
+    config_command(temp_dir, "SELECT name FROM regions;", config)
+        .env("PATH", path)
+        .output()
+        .expect("Failed to execute command")
+}
+
+fn ssh_log(temp_dir: &TempDir) -> Vec<String> {
+    std::fs::read_to_string(temp_dir.path().join("ssh.log"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_entries_share_a_tunnel_per_bastion() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    let bastion = json!({
+        "host": "bastion.example",
+        "port": 2222,
+        "user": "jump",
+        "key": "/keys/id_ed25519",
+        "known_hosts": "accept-new"
+    });
+    let output = run_with_fake_ssh(
+        &temp_dir,
+        json!({
+            "connection_strings": [
+                {"name": "eu_1", "uri": pg_container.uri, "ssh": bastion},
+                {"name": "eu_2", "uri": pg_container.uri, "ssh": bastion}
+            ]
+        }),
+    );
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut results =
+        parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    results.sort_by_key(|row| row["db_name"].to_string());
+
+    let expected = vec![
+        json!({"db_name": "eu_1", "name": "eu-west"}),
+        json!({"db_name": "eu_2", "name": "eu-west"}),
+    ];
+    assert_eq!(results, expected);
+
+    let log = ssh_log(&temp_dir);
+    assert_eq!(log.len(), 1, "{:?}", log);
+    for arg in [
+        "-p 2222",
+        "-l jump",
+        "-i /keys/id_ed25519",
+        "StrictHostKeyChecking=accept-new",
+    ] {
+        assert!(log[0].contains(arg), "{}", log[0]);
+    }
+    assert!(log[0].ends_with(" bastion.example"), "{}", log[0]);
+}
+
+#[tokio::test]
+async fn test_failed_ssh_login_is_reported() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run_with_fake_ssh(
+        &temp_dir,
+        json!({
+            "connection_strings": [{
+                "name": "eu_1",
+                "uri": pg_container.uri,
+                "ssh": {"host": "denied.example"}
+            }]
+        }),
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("SSH tunnel through 'denied.example' failed"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("Permission denied (publickey)"), "{}", stderr);
+}
+
+#[tokio::test]
+async fn test_taken_proxy_port_is_retried() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run_with_fake_ssh(
+        &temp_dir,
+        json!({
+            "connection_strings": [{
+                "name": "eu_1",
+                "uri": pg_container.uri,
+                "ssh": {"host": "busy.example"}
+            }]
+        }),
+    );
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        parse_json_lines(&String::from_utf8_lossy(&output.stdout)),
+        vec![json!({"db_name": "eu_1", "name": "eu-west"})]
+    );
+    assert_eq!(ssh_log(&temp_dir).len(), 2);
+}
+
+#[test]
+fn test_ssh_settings_need_a_postgres_entry() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run_with_fake_ssh(
+        &temp_dir,
+        json!({
+            "connection_strings": [{
+                "name": "local",
+                "uri": format!(
+                    "sqlite://{}",
+                    temp_dir.path().join("local.db").display()
+                ),
+                "ssh": {"host": "bastion.example"}
+            }]
+        }),
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("SSH tunnels for 'local' are only supported"),
+        "{}",
+        stderr
+    );
+    assert!(ssh_log(&temp_dir).is_empty());
+}
@@ -0,0 +1,87 @@
+use super::utils::{
+    build_cli, create_query_file, create_test_postgres_db, parse_json_lines,
+    run_cli,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_expand_database_into_tenant_schemas() {
+    let setup_sql = r#"
+        CREATE TABLE public.orders (id INT, total INT);
+        INSERT INTO public.orders VALUES (1, 0);
+
+        CREATE SCHEMA tenant_acme;
+        CREATE TABLE tenant_acme.orders (id INT, total INT);
+        INSERT INTO tenant_acme.orders VALUES (1, 100), (2, 250);
+
+        CREATE SCHEMA "tenant_Globex";
+        CREATE TABLE "tenant_Globex".orders (id INT, total INT);
+        INSERT INTO "tenant_Globex".orders VALUES (1, 75);
+
+        CREATE SCHEMA tenant_demo;
+        CREATE TABLE tenant_demo.orders (id INT, total INT);
+        INSERT INTO tenant_demo.orders VALUES (1, 999);
+    "#;
+
+    let pg_container = create_test_postgres_db(setup_sql).await;
+
+    let query = "SELECT id, total FROM orders ORDER BY id;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "app".to_string(),
+        format!(
+            "{}?schemas=tenant_*&exclude_schemas=tenant_demo",
+            pg_container.uri
+        ),
+    )];
+    let output = run_cli(&cli_path, query_file.path(), &connection_strings)
+        .expect("CLI execution failed");
+
+    let mut results = parse_json_lines(&output);
+    results.sort_by_key(|row| {
+        (row["schema_name"].to_string(), row["id"].as_i64())
+    });
+
+    let expected = vec![
+        json!({
+            "db_name": "app",
+            "schema_name": "tenant_Globex",
+            "id": 1,
+            "total": 75
+        }),
+        json!({
+            "db_name": "app",
+            "schema_name": "tenant_acme",
+            "id": 1,
+            "total": 100
+        }),
+        json!({
+            "db_name": "app",
+            "schema_name": "tenant_acme",
+            "id": 2,
+            "total": 250
+        }),
+    ];
+
+    assert_eq!(results, expected);
+}
+
+#[tokio::test]
+async fn test_no_matching_schema_fails() {
+    let pg_container = create_test_postgres_db("").await;
+
+    let query = "SELECT 1;";
+    let query_file = create_query_file(query);
+
+    let cli_path = build_cli();
+    let connection_strings = vec![(
+        "app".to_string(),
+        format!("{}?schemas=tenant_*", pg_container.uri),
+    )];
+    let result = run_cli(&cli_path, query_file.path(), &connection_strings);
+
+    let stderr = result.expect_err("Expected the run to fail");
+    assert!(stderr.contains("No schemas match for 'app'"));
+}
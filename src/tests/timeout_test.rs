@@ -0,0 +1,159 @@
+use super::utils::{create_test_postgres_db, parse_json_lines, run_with_args};
+use serde_json::json;
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const SETUP_SQL: &str = r#"
+    CREATE TABLE regions (name TEXT);
+    INSERT INTO regions VALUES ('eu-west');
+"#;
+
+/// `regions` takes a while to read.
+const SLOW_SETUP_SQL: &str = r#"
+    CREATE VIEW regions AS SELECT 'us-east' AS name FROM pg_sleep(10);
+"#;
+
+const QUERY: &str = "SELECT name FROM regions;";
+
+#[tokio::test]
+async fn test_timeout_reports_slow_database() {
+    let up = create_test_postgres_db(SETUP_SQL).await;
+    let slow = create_test_postgres_db(SLOW_SETUP_SQL).await;
+
+    let output = run_with_args(
+        QUERY,
+        &["--timeout", "1", "--on-error", "continue"],
+        &[("up", &up.uri), ("slow", &slow.uri)],
+    );
+
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(results, vec![json!({"db_name": "up", "name": "eu-west"})]);
+
+    let errors = parse_json_lines(&String::from_utf8_lossy(&output.stderr));
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert_eq!(errors[0]["db_name"], "slow");
+    assert_eq!(errors[0]["stage"], "timeout");
+    assert_eq!(
+        errors[1],
+        json!({"summary": {"succeeded": ["up"], "failed": ["slow"]}})
+    );
+}
+
+#[tokio::test]
+async fn test_entry_timeout_overrides_cli() {
+    let slow = create_test_postgres_db(SLOW_SETUP_SQL).await;
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.json");
+    let config = json!({
+        "connection_strings": [{
+            "name": "slow",
+            "uri": slow.uri,
+            "timeout": 1
+        }]
+    });
+    std::fs::write(&config_path, config.to_string()).unwrap();
+
+    let started = Instant::now();
+    let output = run_with_args(
+        QUERY,
+        &["--timeout", "60", "--config", config_path.to_str().unwrap()],
+        &[],
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(started.elapsed() < Duration::from_secs(10));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("statement timeout")
+            || stderr.contains("Query timed out after 1s"),
+        "{}",
+        stderr
+    );
+}
+
+#[tokio::test]
+async fn test_deadline_stops_the_run() {
+    let up = create_test_postgres_db(SETUP_SQL).await;
+    let slow = create_test_postgres_db(SLOW_SETUP_SQL).await;
+
+    let started = Instant::now();
+    let output = run_with_args(
+        QUERY,
+        &["--deadline", "2", "--on-error", "continue"],
+        &[("up", &up.uri), ("slow", &slow.uri)],
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(started.elapsed() < Duration::from_secs(10));
+
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(results, vec![json!({"db_name": "up", "name": "eu-west"})]);
+
+    let errors = parse_json_lines(&String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        errors[0],
+        json!({
+            "db_name": "slow",
+            "stage": "timeout",
+            "message": "Deadline of 2s for the run passed"
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_deadline_counts_from_before_connecting() {
+    let up = create_test_postgres_db(SETUP_SQL).await;
+    // Accepts connections but never answers, like a blackholed host
+    let silent = TcpListener::bind("127.0.0.1:0").unwrap();
+    let silent_uri = format!(
+        "postgres://postgres@{}/postgres",
+        silent.local_addr().unwrap()
+    );
+
+    let started = Instant::now();
+    let output = run_with_args(
+        QUERY,
+        &["--deadline", "1", "--on-error", "continue"],
+        &[("up", &up.uri), ("silent", &silent_uri)],
+    );
+
+    // Connecting to `silent` takes up the whole deadline, so `up` is out
+    // of time before its query starts
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert!(String::from_utf8_lossy(&output.stdout).is_empty());
+    let errors = parse_json_lines(&String::from_utf8_lossy(&output.stderr));
+    for db_name in ["silent", "up"] {
+        assert!(
+            errors.contains(&json!({
+                "db_name": db_name,
+                "stage": "timeout",
+                "message": "Deadline of 1s for the run passed"
+            })),
+            "{:?}",
+            errors
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_invalid_timeout_fails() {
+    let up = create_test_postgres_db(SETUP_SQL).await;
+
+    let output = run_with_args(QUERY, &["--timeout", "0"], &[("up", &up.uri)]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Invalid timeout for 'up' of 0 seconds"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,181 @@
+use super::utils::{
+    build_cli, create_query_file, create_test_postgres_db, parse_json_lines,
+    unreachable,
+};
+use serde_json::json;
+use std::process::Command;
+use tempfile::TempDir;
+use url::Url;
+
+const SETUP_SQL: &str = r#"
+    CREATE TABLE regions (name TEXT);
+    INSERT INTO regions VALUES ('eu-west');
+"#;
+
+const QUERY: &str = "SELECT name FROM regions;";
+
+#[tokio::test]
+async fn test_skip_unreachable_reports_error_records() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let query_file = create_query_file(QUERY);
+
+    let cli_path = build_cli();
+    let output = Command::new(&cli_path)
+        .arg("--query")
+        .arg(query_file.path())
+        .arg("--skip-unreachable")
+        .args([
+            "--connection-string",
+            &format!("up,{}", pg_container.uri),
+            "--connection-string",
+            &format!("down,{}", unreachable(&pg_container.uri)),
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(results, vec![json!({"db_name": "up", "name": "eu-west"})]);
+
+    let errors = parse_json_lines(&String::from_utf8_lossy(&output.stderr));
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert_eq!(errors[0]["db_name"], "down");
+    assert_eq!(errors[0]["stage"], "connect");
+    assert!(!errors[0]["message"].as_str().unwrap().is_empty());
+    assert_eq!(
+        errors[1],
+        json!({"summary": {"succeeded": ["up"], "failed": ["down"]}})
+    );
+}
+
+#[tokio::test]
+async fn test_error_records_go_to_error_file() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let query_file = create_query_file(QUERY);
+    let temp_dir = TempDir::new().unwrap();
+    let error_file = temp_dir.path().join("errors.jsonl");
+
+    let cli_path = build_cli();
+    let output = Command::new(&cli_path)
+        .arg("--query")
+        .arg(query_file.path())
+        .arg("--skip-unreachable")
+        .arg("--error-file")
+        .arg(&error_file)
+        .args([
+            "--connection-string",
+            &format!("up,{}", pg_container.uri),
+            "--connection-string",
+            &format!("down,{}", unreachable(&pg_container.uri)),
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        parse_json_lines(&String::from_utf8_lossy(&output.stderr)).is_empty()
+    );
+
+    let errors =
+        parse_json_lines(&std::fs::read_to_string(&error_file).unwrap());
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert_eq!(errors[0]["db_name"], "down");
+    assert_eq!(errors[0]["stage"], "connect");
+}
+
+#[tokio::test]
+async fn test_skip_unreachable_covers_server_patterns() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let query_file = create_query_file(QUERY);
+
+    let mut fleet = Url::parse(&unreachable(&pg_container.uri)).unwrap();
+    fleet.set_path("/tenant_*");
+
+    let cli_path = build_cli();
+    let output = Command::new(&cli_path)
+        .arg("--query")
+        .arg(query_file.path())
+        .arg("--skip-unreachable")
+        .args([
+            "--connection-string",
+            &format!("up,{}", pg_container.uri),
+            "--connection-string",
+            &format!("fleet,{}", fleet),
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let results = parse_json_lines(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(results, vec![json!({"db_name": "up", "name": "eu-west"})]);
+
+    let errors = parse_json_lines(&String::from_utf8_lossy(&output.stderr));
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert_eq!(errors[0]["db_name"], "fleet");
+    assert_eq!(errors[0]["stage"], "connect");
+    assert_eq!(
+        errors[1],
+        json!({"summary": {"succeeded": ["up"], "failed": ["fleet"]}})
+    );
+}
+
+#[tokio::test]
+async fn test_unreachable_database_aborts_by_default() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let query_file = create_query_file(QUERY);
+
+    let cli_path = build_cli();
+    let output = Command::new(&cli_path)
+        .arg("--query")
+        .arg(query_file.path())
+        .args([
+            "--connection-string",
+            &format!("up,{}", pg_container.uri),
+            "--connection-string",
+            &format!("down,{}", unreachable(&pg_container.uri)),
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).is_empty());
+}
+
+#[tokio::test]
+async fn test_skip_unreachable_fails_without_any_database() {
+    let pg_container = create_test_postgres_db(SETUP_SQL).await;
+    let query_file = create_query_file(QUERY);
+
+    let cli_path = build_cli();
+    let output = Command::new(&cli_path)
+        .arg("--query")
+        .arg(query_file.path())
+        .arg("--skip-unreachable")
+        .args([
+            "--connection-string",
+            &format!("down,{}", unreachable(&pg_container.uri)),
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("None of the databases could be connected"),
+        "{}",
+        stderr
+    );
+    assert_eq!(parse_json_lines(&stderr).len(), 1);
+}
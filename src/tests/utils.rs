@@ -1,11 +1,19 @@
+use parquet::data_type::{ByteArray, ByteArrayType, Int32Type, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
 use serde_json::Value;
-use std::io::Write;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use tempfile::NamedTempFile;
+use std::process::{Command, Output};
+use std::sync::Arc;
+use tempfile::{NamedTempFile, TempDir};
 use testcontainers::core::IntoContainerPort;
 use testcontainers::{ContainerAsync, runners::AsyncRunner};
+use testcontainers_modules::mysql::Mysql;
 use testcontainers_modules::postgres::Postgres;
+use url::Url;
 
 pub fn create_query_file(query: &str) -> NamedTempFile {
     let mut file =
@@ -57,6 +65,224 @@ pub fn parse_json_lines(output: &str) -> Vec<Value> {
         .collect()
 }
 
+/// Runs `query` against every `name,uri` pair with the extra `args`.
+pub fn run_with_args(
+    query: &str,
+    args: &[&str],
+    connection_strings: &[(&str, &str)],
+) -> Output {
+    let query_file = create_query_file(query);
+
+    let mut command = Command::new(build_cli());
+    command.arg("--query").arg(query_file.path()).args(args);
+    for (name, uri) in connection_strings {
+        command.arg("--connection-string").arg(format!("{},{}", name, uri));
+    }
+
+    command.output().expect("Failed to execute command")
+}
+
+/// Writes `query` and `config` into `temp_dir` and returns the command that
+/// runs one against the other, for tests that need to adjust it first.
+pub fn config_command(
+    temp_dir: &TempDir,
+    query: &str,
+    config: Value,
+) -> Command {
+    let config_path = temp_dir.path().join("config.json");
+    std::fs::write(&config_path, config.to_string()).unwrap();
+
+    let query_path = temp_dir.path().join("query.sql");
+    std::fs::write(&query_path, query).unwrap();
+
+    let mut command = Command::new(build_cli());
+    command.arg("--query").arg(query_path).arg("--config").arg(config_path);
+    command
+}
+
+pub fn run_with_config(
+    temp_dir: &TempDir,
+    query: &str,
+    config: Value,
+) -> Output {
+    config_command(temp_dir, query, config)
+        .output()
+        .expect("Failed to execute command")
+}
+
+/// Same server, nothing listens on port 1.
+pub fn unreachable(uri: &str) -> String {
+    let mut url = Url::parse(uri).unwrap();
+    url.set_port(Some(1)).unwrap();
+    url.to_string()
+}
+
+pub async fn create_test_sqlite_db(path: &Path, setup_sql: &str) {
+    let uri = format!("sqlite://{}?mode=rwc", path.display());
+    let pool = sqlx::SqlitePool::connect(&uri)
+        .await
+        .expect("Failed to create SQLite database");
+
+    sqlx::raw_sql(setup_sql)
+        .execute(&pool)
+        .await
+        .expect("Failed to run SQLite setup");
+
+    pool.close().await;
+}
+
+pub fn create_test_duckdb_db(path: &Path, setup_sql: &str) {
+    let output = Command::new("duckdb")
+        .arg(path)
+        .arg("-c")
+        .arg(setup_sql)
+        .output()
+        .expect("Failed to run duckdb CLI");
+
+    if !output.status.success() {
+        panic!(
+            "Failed to run DuckDB setup: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+/// A stand-in HTTP server that answers every request with the response
+/// `respond` returns for the request body. Returns the `host:port` it listens
+/// on and keeps serving until the test process exits.
+pub fn start_canned_http_server(
+    respond: impl Fn(&str) -> (u16, String) + Send + 'static,
+) -> String {
+    let listener =
+        TcpListener::bind("127.0.0.1:0").expect("Failed to bind listener");
+    let address = listener.local_addr().unwrap().to_string();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    break;
+                }
+                if let Some((header, value)) = line.split_once(':')
+                    && header.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+                if line == "\r\n" {
+                    break;
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).ok();
+
+            let (status, response) = respond(&String::from_utf8_lossy(&body));
+            write!(
+                stream,
+                "HTTP/1.1 {} Canned\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                response.len(),
+                response
+            )
+            .ok();
+        }
+    });
+
+    address
+}
+
+/// Writes a two row Parquet file with timestamp, decimal, list and struct
+/// columns. The second row leaves every optional column empty.
+pub fn create_test_parquet_file(path: &Path) {
+    let schema = parse_message_type(
+        "message schema {
+            REQUIRED INT64 id;
+            REQUIRED BYTE_ARRAY name (UTF8);
+            OPTIONAL INT64 created_at (TIMESTAMP(MILLIS,true));
+            OPTIONAL INT32 price (DECIMAL(9,2));
+            OPTIONAL group tags (LIST) {
+                REPEATED group list {
+                    OPTIONAL BYTE_ARRAY element (UTF8);
+                }
+            }
+            OPTIONAL group dims {
+                REQUIRED INT32 width;
+                REQUIRED INT32 height;
+            }
+        }",
+    )
+    .expect("Invalid Parquet schema");
+
+    let file = std::fs::File::create(path).expect("Failed to create file");
+    let mut writer = SerializedFileWriter::new(
+        file,
+        Arc::new(schema),
+        Arc::new(WriterProperties::builder().build()),
+    )
+    .expect("Failed to create Parquet writer");
+    let mut row_group = writer.next_row_group().unwrap();
+
+    let mut column = row_group.next_column().unwrap().unwrap();
+    column.typed::<Int64Type>().write_batch(&[1, 2], None, None).unwrap();
+    column.close().unwrap();
+
+    let mut column = row_group.next_column().unwrap().unwrap();
+    column
+        .typed::<ByteArrayType>()
+        .write_batch(
+            &[ByteArray::from("Alice"), ByteArray::from("Bob")],
+            None,
+            None,
+        )
+        .unwrap();
+    column.close().unwrap();
+
+    // 2024-01-15T10:30:00Z
+    let mut column = row_group.next_column().unwrap().unwrap();
+    column
+        .typed::<Int64Type>()
+        .write_batch(&[1_705_314_600_000], Some(&[1, 0]), None)
+        .unwrap();
+    column.close().unwrap();
+
+    let mut column = row_group.next_column().unwrap().unwrap();
+    column
+        .typed::<Int32Type>()
+        .write_batch(&[1999], Some(&[1, 0]), None)
+        .unwrap();
+    column.close().unwrap();
+
+    let mut column = row_group.next_column().unwrap().unwrap();
+    column
+        .typed::<ByteArrayType>()
+        .write_batch(
+            &[ByteArray::from("new"), ByteArray::from("sale")],
+            Some(&[3, 3, 0]),
+            Some(&[0, 1, 0]),
+        )
+        .unwrap();
+    column.close().unwrap();
+
+    for value in [3, 4] {
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int32Type>()
+            .write_batch(&[value], Some(&[1, 0]), None)
+            .unwrap();
+        column.close().unwrap();
+    }
+
+    row_group.close().unwrap();
+    writer.close().unwrap();
+}
+
 pub struct PostgresContainer {
     _container: ContainerAsync<Postgres>,
     pub uri: String,
@@ -82,3 +308,28 @@ pub async fn create_test_postgres_db(setup_sql: &str) -> PostgresContainer {
 
     PostgresContainer { _container: container, uri }
 }
+
+pub struct MysqlContainer {
+    _container: ContainerAsync<Mysql>,
+    pub uri: String,
+}
+
+pub async fn create_test_mysql_db(setup_sql: &str) -> MysqlContainer {
+    let mysql = if setup_sql.is_empty() {
+        Mysql::default()
+    } else {
+        Mysql::default().with_init_sql(setup_sql.as_bytes().to_vec())
+    };
+
+    let container =
+        mysql.start().await.expect("Failed to start MySQL container");
+
+    let host = container.get_host().await.expect("Failed to get host");
+    let port = container
+        .get_host_port_ipv4(3306.tcp())
+        .await
+        .expect("Failed to get port");
+    let uri = format!("mysql://root@{}:{}/test", host, port);
+
+    MysqlContainer { _container: container, uri }
+}